@@ -3,16 +3,13 @@ extern crate piston;
 extern crate tcod;
 extern crate tcod_window;
 
-use std::cell::RefCell;
-use std::rc::Rc;
-
 use fps_counter::FPSCounter;
 use piston::event_loop::{EventLoop, Events};
 use piston::input::Event::{Render, Update};
 use piston::window::{Size, WindowSettings};
 use tcod::Console;
-use tcod::console::{Renderer, Root};
-use tcod_window::TcodWindow;
+use tcod::console::Renderer;
+use tcod_window::TcodWindowBuilder;
 
 const WINDOW_TITLE: &'static str = "fps_counter";
 const WINDOW_SIZE_HEIGHT: u32 = 50;
@@ -26,15 +23,10 @@ fn main() {
                                        })
                        .exit_on_esc(true);
 
-    let root = Root::initializer()
-                      .size(settings.get_size().width as i32,
-                            settings.get_size().height as i32)
-                      .title(settings.get_title())
-                      .renderer(Renderer::SDL)
-                      .init();
-    let console = Rc::new(RefCell::new(root));
-
-    let mut window = TcodWindow::with_console(console, settings);
+    let mut window = TcodWindowBuilder::new(settings)
+                         .renderer(Renderer::SDL)
+                         .build()
+                         .expect("Failed to create window");
     let mut events = window.events().ups(140).max_fps(10000);
 
     let mut fps_counter = FPSCounter::new();
@@ -0,0 +1,240 @@
+// Copyright 2015-2016 Nikita Pekin and the tcod_window contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::cmp;
+use std::ops::{Deref, DerefMut};
+
+use tcod::Console;
+use tcod::console::{self, Offscreen, Root};
+
+/// The glyph size reported by a `Headless` console, in pixels.
+pub const HEADLESS_GLYPH_SIZE: (i32, i32) = (8, 8);
+
+/// The screen resolution a `Headless` console pretends to go fullscreen on, in
+/// pixels.
+pub const HEADLESS_SCREEN_SIZE: (i32, i32) = (1280, 720);
+
+/// A console which a `TcodWindow` can present.
+pub trait Backend {
+    /// Returns the width and height of the console, in cells.
+    fn console_size(&self) -> (i32, i32);
+
+    /// Returns the width and height of a single console cell, in pixels.
+    fn char_size(&self) -> (i32, i32);
+
+    /// Returns the width and height of the window the console is shown in, in
+    /// pixels.
+    ///
+    /// In fullscreen mode, this includes the borders around the console.
+    fn window_size(&self) -> (i32, i32);
+
+    /// Presents the contents of the console.
+    fn flush(&mut self);
+
+    /// Sets the title of the window the console is shown in.
+    fn set_title(&mut self, title: &str);
+
+    /// Returns whether the user has closed the window the console is shown in.
+    fn window_closed(&self) -> bool;
+
+    /// Returns whether the window the console is shown in has mouse focus,
+    /// i.e. the mouse pointer is over it.
+    fn has_focus(&self) -> bool;
+
+    /// Returns whether the window the console is shown in is active, i.e. has
+    /// keyboard focus and is not minimized.
+    fn is_active(&self) -> bool;
+
+    /// Returns whether the console is shown fullscreen.
+    fn is_fullscreen(&self) -> bool;
+
+    /// Switches the console in or out of fullscreen mode.
+    fn set_fullscreen(&mut self, value: bool);
+
+    /// Shows or hides the mouse cursor.
+    fn show_cursor(&mut self, value: bool);
+
+    /// Moves the mouse cursor to the given position, in pixels.
+    fn move_cursor(&mut self, x: i32, y: i32);
+}
+
+impl Backend for Root {
+    fn console_size(&self) -> (i32, i32) {
+        (self.width(), self.height())
+    }
+
+    fn char_size(&self) -> (i32, i32) {
+        ::tcod::system::get_char_size()
+    }
+
+    fn window_size(&self) -> (i32, i32) {
+        let (char_width, char_height) = self.char_size();
+        let (width, height) = (self.width() * char_width, self.height() * char_height);
+        if !self.is_fullscreen() {
+            return (width, height);
+        }
+
+        // TCOD centers the console on a screen mode at least as large as it,
+        // leaving the same border on either side.
+        let (offset_x, offset_y) = ::tcod::system::get_fullscreen_offsets();
+        (width + 2 * offset_x, height + 2 * offset_y)
+    }
+
+    fn flush(&mut self) {
+        Root::flush(self);
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.set_window_title(title);
+    }
+
+    fn window_closed(&self) -> bool {
+        Root::window_closed(self)
+    }
+
+    fn has_focus(&self) -> bool {
+        Root::has_focus(self)
+    }
+
+    fn is_active(&self) -> bool {
+        Root::is_active(self)
+    }
+
+    fn is_fullscreen(&self) -> bool {
+        Root::is_fullscreen(self)
+    }
+
+    fn set_fullscreen(&mut self, value: bool) {
+        Root::set_fullscreen(self, value);
+    }
+
+    fn show_cursor(&mut self, value: bool) {
+        ::tcod::input::show_cursor(value);
+    }
+
+    fn move_cursor(&mut self, x: i32, y: i32) {
+        ::tcod::input::move_cursor(x, y);
+    }
+}
+
+/// A console which is never shown on screen.
+///
+/// It draws into an offscreen console, so it can be used where no display is
+/// available, e.g. in tests. Events can be fed to a headless window through
+/// `TcodWindow::set_event_source`.
+///
+/// The offscreen console is accessible through `Deref`.
+pub struct Headless {
+    console: Offscreen,
+    closed: bool,
+    mouse_focused: bool,
+    active: bool,
+    fullscreen: bool,
+}
+
+impl Headless {
+    /// Creates a new headless console of the given size, in cells.
+    pub fn new(width: i32, height: i32) -> Self {
+        Headless {
+            console: Offscreen::new(width, height),
+            closed: false,
+            mouse_focused: true,
+            active: true,
+            fullscreen: false,
+        }
+    }
+
+    /// Resizes the console to the given size, in cells, keeping the contents
+    /// which still fit.
+    pub fn resize(&mut self, width: i32, height: i32) {
+        let mut resized = Offscreen::new(width, height);
+        let kept = (cmp::min(width, self.console.width()),
+                    cmp::min(height, self.console.height()));
+        console::blit(&self.console, (0, 0), kept, &mut resized, (0, 0), 1.0, 1.0);
+        self.console = resized;
+    }
+
+    /// Simulates the user closing the window.
+    pub fn close(&mut self) {
+        self.closed = true;
+    }
+
+    /// Simulates the mouse pointer entering or leaving the window.
+    pub fn set_mouse_focus(&mut self, value: bool) {
+        self.mouse_focused = value;
+    }
+
+    /// Simulates the window gaining or losing keyboard focus, or being
+    /// restored or minimized.
+    pub fn set_active(&mut self, value: bool) {
+        self.active = value;
+    }
+}
+
+impl Deref for Headless {
+    type Target = Offscreen;
+
+    fn deref(&self) -> &Offscreen {
+        &self.console
+    }
+}
+
+impl DerefMut for Headless {
+    fn deref_mut(&mut self) -> &mut Offscreen {
+        &mut self.console
+    }
+}
+
+impl Backend for Headless {
+    fn console_size(&self) -> (i32, i32) {
+        (self.console.width(), self.console.height())
+    }
+
+    fn char_size(&self) -> (i32, i32) {
+        HEADLESS_GLYPH_SIZE
+    }
+
+    fn window_size(&self) -> (i32, i32) {
+        let (width, height) = (self.console.width() * HEADLESS_GLYPH_SIZE.0,
+                               self.console.height() * HEADLESS_GLYPH_SIZE.1);
+        if !self.fullscreen {
+            return (width, height);
+        }
+
+        (cmp::max(width, HEADLESS_SCREEN_SIZE.0), cmp::max(height, HEADLESS_SCREEN_SIZE.1))
+    }
+
+    fn flush(&mut self) {}
+
+    fn set_title(&mut self, _title: &str) {}
+
+    fn window_closed(&self) -> bool {
+        self.closed
+    }
+
+    fn has_focus(&self) -> bool {
+        self.mouse_focused
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn is_fullscreen(&self) -> bool {
+        self.fullscreen
+    }
+
+    fn set_fullscreen(&mut self, value: bool) {
+        self.fullscreen = value;
+    }
+
+    fn show_cursor(&mut self, _value: bool) {}
+
+    fn move_cursor(&mut self, _x: i32, _y: i32) {}
+}
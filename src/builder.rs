@@ -0,0 +1,246 @@
+// Copyright 2015-2016 Nikita Pekin and the tcod_window contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::cell::RefCell;
+use std::cmp;
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use tcod::console::{FontLayout, FontType, Renderer, Root};
+use window::{Size, WindowSettings};
+
+use {Error, KeyMap, TcodWindow};
+
+/// The font TCOD loads when no other font is configured.
+const DEFAULT_FONT: &'static str = "terminal.png";
+
+/// The font options the TCOD `Root` console is initialized with.
+#[derive(Default)]
+pub struct FontConfig {
+    /// The font file and the layout of the glyphs within it.
+    pub font: Option<(PathBuf, FontLayout)>,
+    /// The type of the font file.
+    pub font_type: Option<FontType>,
+}
+
+impl FontConfig {
+    /// Returns the path of the font file TCOD will load.
+    pub fn path(&self) -> PathBuf {
+        match self.font {
+            Some((ref path, _)) => path.clone(),
+            None => PathBuf::from(DEFAULT_FONT),
+        }
+    }
+}
+
+/// A builder for `TcodWindow`s, exposing TCOD-specific options which are not
+/// covered by `WindowSettings`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate piston;
+/// # extern crate tcod;
+/// # extern crate tcod_window;
+/// #
+/// use piston::window::{Size, WindowSettings};
+/// use tcod::console::{FontLayout, Renderer};
+/// use tcod_window::TcodWindowBuilder;
+///
+/// # fn main() {
+/// let settings = WindowSettings::new(
+///     "My Application".to_owned(),
+///     Size {
+///         width: 100,
+///         height: 100,
+///     }
+/// );
+///
+/// let mut window = TcodWindowBuilder::new(settings)
+///                      .font("terminal.png", FontLayout::AsciiInCol)
+///                      .renderer(Renderer::SDL)
+///                      .fps(60)
+///                      .build()
+///                      .expect("Failed to build window.");
+/// # }
+/// ```
+pub struct TcodWindowBuilder {
+    settings: WindowSettings,
+    font: FontConfig,
+    renderer: Option<Renderer>,
+    fps: Option<u32>,
+    fullscreen: bool,
+    key_map: KeyMap,
+}
+
+impl TcodWindowBuilder {
+    /// Creates a new builder seeded from the provided `WindowSettings`.
+    ///
+    /// Options not set on the builder are left at TCOD's defaults.
+    pub fn new(settings: WindowSettings) -> Self {
+        TcodWindowBuilder {
+            fullscreen: settings.get_fullscreen(),
+            settings: settings,
+            font: FontConfig::default(),
+            renderer: None,
+            fps: None,
+            key_map: KeyMap::default(),
+        }
+    }
+
+    /// Sets the font file used to render the console, and the layout of the
+    /// glyphs within it.
+    pub fn font<P: AsRef<Path>>(mut self, path: P, layout: FontLayout) -> Self {
+        self.font.font = Some((path.as_ref().to_owned(), layout));
+        self
+    }
+
+    /// Sets the type of the font file.
+    pub fn font_type(mut self, value: FontType) -> Self {
+        self.font.font_type = Some(value);
+        self
+    }
+
+    /// Sets the renderer used to draw the console.
+    ///
+    /// If the renderer is unavailable, TCOD falls back to a simpler one, in
+    /// GLSL, OpenGL, SDL order. The renderer in use is reported by
+    /// `TcodWindow::renderer`. Defaults to the renderer of
+    /// `Root::initializer`.
+    pub fn renderer(mut self, value: Renderer) -> Self {
+        self.renderer = Some(value);
+        self
+    }
+
+    /// Limits the number of frames rendered per second.
+    ///
+    /// Zero leaves the frame rate unlimited. TCOD takes the limit as an `i32`,
+    /// so larger values are clamped to `i32::MAX`.
+    pub fn fps(mut self, value: u32) -> Self {
+        self.fps = Some(value);
+        self
+    }
+
+    /// Sets whether the window starts in fullscreen mode.
+    ///
+    /// Overrides the value taken from the `WindowSettings`.
+    pub fn fullscreen(mut self, value: bool) -> Self {
+        self.fullscreen = value;
+        self
+    }
+
+    /// Sets the key map used to translate TCOD keys.
+    pub fn key_map(mut self, value: KeyMap) -> Self {
+        self.key_map = value;
+        self
+    }
+
+    /// Initializes the TCOD `Root` console and creates the window.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the console size is zero or too large, if the font
+    /// file does not exist, or if no display is available. These are checked
+    /// up front, since TCOD aborts the process when it fails to initialize.
+    pub fn build(self) -> Result<TcodWindow, Error> {
+        try!(self.check());
+
+        // TCOD quietly switches to a simpler renderer when the requested one
+        // fails to initialize, so there is nothing left to retry here.
+        let console = init_root(self.settings.get_size(),
+                                &self.settings.get_title(),
+                                self.fullscreen,
+                                self.renderer,
+                                &self.font);
+
+        if let Some(fps) = self.fps {
+            ::tcod::system::set_fps(cmp::min(fps, i32::max_value() as u32) as i32);
+        }
+
+        let mut window = TcodWindow::with_console(Rc::new(RefCell::new(console)), self.settings);
+        window.font = Some(self.font);
+        window.key_map = self.key_map;
+        Ok(window)
+    }
+
+    /// Checks that the TCOD `Root` console can be initialized with the current
+    /// options.
+    fn check(&self) -> Result<(), Error> {
+        try!(check_size(self.settings.get_size()));
+
+        let font = self.font.path();
+        if !font.is_file() {
+            return Err(Error::FontNotFound(font));
+        }
+
+        if !display_available() {
+            return Err(Error::NoDisplay);
+        }
+
+        Ok(())
+    }
+}
+
+/// Initializes the TCOD `Root` console.
+pub fn init_root(size: Size,
+                 title: &str,
+                 fullscreen: bool,
+                 renderer: Option<Renderer>,
+                 font: &FontConfig)
+                 -> Root {
+    let mut initializer = Root::initializer();
+    initializer.size(size.width as i32, size.height as i32)
+               .title(title)
+               .fullscreen(fullscreen);
+    if let Some(renderer) = renderer {
+        initializer.renderer(renderer);
+    }
+    if let Some((ref path, layout)) = font.font {
+        initializer.font(path.clone(), layout);
+    }
+    if let Some(font_type) = font.font_type {
+        initializer.font_type(font_type);
+    }
+    initializer.init()
+}
+
+/// Checks that a console of the given size, in cells, can be created.
+pub fn check_size(size: Size) -> Result<(), Error> {
+    let max = i32::max_value() as u32;
+    if size.width == 0 || size.height == 0 || size.width > max || size.height > max {
+        return Err(Error::InvalidSize {
+            width: size.width,
+            height: size.height,
+        });
+    }
+
+    Ok(())
+}
+
+/// Returns whether a display is available to open a window on.
+///
+/// TCOD exits the process when it fails to create its window, so this has to
+/// be judged from the environment before initializing it. Only X11 and Wayland
+/// systems need the display to be named in the environment.
+fn display_available() -> bool {
+    !cfg!(all(unix, not(target_os = "macos"))) || has_display(|name| env::var_os(name))
+}
+
+/// Returns whether the environment, read through `var`, provides a display.
+///
+/// Besides a display server, an explicitly chosen SDL video driver counts as a
+/// display, since drivers such as `dummy`, `fbcon` or `kmsdrm` work without
+/// one.
+pub fn has_display<F: Fn(&str) -> Option<OsString>>(var: F) -> bool {
+    ["DISPLAY", "WAYLAND_DISPLAY", "SDL_VIDEODRIVER"]
+        .iter()
+        .any(|name| var(name).map_or(false, |value| !value.is_empty()))
+}
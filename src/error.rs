@@ -0,0 +1,87 @@
+// Copyright 2015-2016 Nikita Pekin and the tcod_window contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// An error raised while creating or re-initializing a `TcodWindow`, or while
+/// loading a `KeyMap`.
+#[derive(Debug)]
+pub enum Error {
+    /// The window was created from an existing TCOD console, whose font is
+    /// unknown, so the console cannot be re-initialized.
+    ExternalConsole,
+    /// The font file does not exist.
+    FontNotFound(PathBuf),
+    /// A key map file is malformed.
+    InvalidKeyMap {
+        /// The line of the malformed binding, starting at 1.
+        line: usize,
+        /// A description of what is wrong with the binding.
+        message: String,
+    },
+    /// The requested console size is zero or too large for TCOD.
+    InvalidSize {
+        /// The requested width, in cells.
+        width: u32,
+        /// The requested height, in cells.
+        height: u32,
+    },
+    /// An I/O error occurred while reading a file.
+    Io(io::Error),
+    /// No display is available to open the window on.
+    NoDisplay,
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::ExternalConsole => {
+                write!(f, "cannot re-initialize a console created outside the window")
+            },
+            Error::FontNotFound(ref path) => write!(f, "font file not found: {}", path.display()),
+            Error::InvalidKeyMap { line, ref message } => {
+                write!(f, "invalid key map on line {}: {}", line, message)
+            },
+            Error::InvalidSize { width, height } => {
+                write!(f, "invalid console size: {}x{}", width, height)
+            },
+            Error::Io(ref err) => write!(f, "I/O error: {}", err),
+            Error::NoDisplay => write!(f, "no display available"),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::ExternalConsole => "cannot re-initialize an external console",
+            Error::FontNotFound(_) => "font file not found",
+            Error::InvalidKeyMap { .. } => "invalid key map",
+            Error::InvalidSize { .. } => "invalid console size",
+            Error::Io(ref err) => err.description(),
+            Error::NoDisplay => "no display available",
+        }
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        match *self {
+            Error::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright 2015-2016 Nikita Pekin and the tcod_window contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::cmp;
+use std::collections::VecDeque;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tcod::input::{ANY, Event, EventFlags, check_for_event, wait_for_event};
+
+/// How often `EventSource::wait_event` checks for new events by default, in
+/// milliseconds.
+const POLL_INTERVAL_MS: u64 = 5;
+
+/// A source of raw TCOD events for a `TcodWindow` to translate.
+///
+/// `TcodEventSource` reads events from the live TCOD window. Other
+/// implementations can supply events from mocks, recordings or the network.
+/// A `VecDeque` of events is itself an event source which yields its events in
+/// order.
+pub trait EventSource {
+    /// Returns the next pending event, if any, without blocking.
+    fn next_event(&mut self) -> Option<(EventFlags, Event)>;
+
+    /// Blocks until an event is available and returns it, giving up after
+    /// `timeout` if one is given.
+    ///
+    /// Returns `None` if the timeout elapsed, or if no more events can arrive.
+    /// The default implementation polls `next_event` until the deadline.
+    fn wait_event(&mut self, timeout: Option<Duration>) -> Option<(EventFlags, Event)> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let interval = Duration::from_millis(POLL_INTERVAL_MS);
+
+        loop {
+            if let Some(event) = self.next_event() {
+                return Some(event);
+            }
+
+            match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return None;
+                    }
+                    thread::sleep(cmp::min(interval, deadline - now));
+                },
+                None => thread::sleep(interval),
+            }
+        }
+    }
+}
+
+/// An event source reading events from the TCOD `Root` console.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TcodEventSource;
+
+impl EventSource for TcodEventSource {
+    fn next_event(&mut self) -> Option<(EventFlags, Event)> {
+        check_for_event(ANY)
+    }
+
+    fn wait_event(&mut self, timeout: Option<Duration>) -> Option<(EventFlags, Event)> {
+        match timeout {
+            // TCOD only offers an unbounded wait, which also returns when the
+            // window is closed.
+            None => wait_for_event(ANY, false),
+            Some(_) => {
+                let mut polling = PollingSource(self);
+                polling.wait_event(timeout)
+            },
+        }
+    }
+}
+
+/// Wraps an event source to use the default, polling implementation of
+/// `EventSource::wait_event`.
+struct PollingSource<'a, S: 'a>(&'a mut S);
+
+impl<'a, S: EventSource> EventSource for PollingSource<'a, S> {
+    fn next_event(&mut self) -> Option<(EventFlags, Event)> {
+        self.0.next_event()
+    }
+}
+
+impl EventSource for VecDeque<(EventFlags, Event)> {
+    fn next_event(&mut self) -> Option<(EventFlags, Event)> {
+        self.pop_front()
+    }
+
+    fn wait_event(&mut self, _timeout: Option<Duration>) -> Option<(EventFlags, Event)> {
+        // No more events can arrive while waiting, so there is no point in
+        // blocking.
+        self.pop_front()
+    }
+}
+
+impl<S: EventSource + ?Sized> EventSource for Box<S> {
+    fn next_event(&mut self) -> Option<(EventFlags, Event)> {
+        (**self).next_event()
+    }
+
+    fn wait_event(&mut self, timeout: Option<Duration>) -> Option<(EventFlags, Event)> {
+        (**self).wait_event(timeout)
+    }
+}
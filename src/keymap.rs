@@ -0,0 +1,548 @@
+// Copyright 2015-2016 Nikita Pekin and the tcod_window contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use input::keyboard::Key as PistonKey;
+use tcod::input::{Key as TcodKey, KeyCode};
+
+use Error;
+
+/// Every TCOD key code.
+pub static KEY_CODES: &'static [KeyCode] = &[
+    KeyCode::NoKey,
+    KeyCode::Escape,
+    KeyCode::Backspace,
+    KeyCode::Tab,
+    KeyCode::Enter,
+    KeyCode::Shift,
+    KeyCode::Control,
+    KeyCode::Alt,
+    KeyCode::Pause,
+    KeyCode::CapsLock,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::End,
+    KeyCode::Home,
+    KeyCode::Up,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Down,
+    KeyCode::PrintScreen,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::LeftWin,
+    KeyCode::RightWin,
+    KeyCode::Apps,
+    KeyCode::Number0,
+    KeyCode::Number1,
+    KeyCode::Number2,
+    KeyCode::Number3,
+    KeyCode::Number4,
+    KeyCode::Number5,
+    KeyCode::Number6,
+    KeyCode::Number7,
+    KeyCode::Number8,
+    KeyCode::Number9,
+    KeyCode::NumPad0,
+    KeyCode::NumPad1,
+    KeyCode::NumPad2,
+    KeyCode::NumPad3,
+    KeyCode::NumPad4,
+    KeyCode::NumPad5,
+    KeyCode::NumPad6,
+    KeyCode::NumPad7,
+    KeyCode::NumPad8,
+    KeyCode::NumPad9,
+    KeyCode::NumPadAdd,
+    KeyCode::NumPadSubtract,
+    KeyCode::NumPadDivide,
+    KeyCode::NumPadMultiply,
+    KeyCode::NumPadDecimal,
+    KeyCode::NumPadEnter,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::NumLock,
+    KeyCode::ScrollLock,
+    KeyCode::Spacebar,
+    KeyCode::Char,
+];
+
+/// Returns the TCOD key code with the given name, as formatted by `Debug`.
+pub fn key_code_from_name(name: &str) -> Option<KeyCode> {
+    KEY_CODES.iter().cloned().find(|code| format!("{:?}", code) == name)
+}
+
+/// The TCOD key codes of the digits above the letters.
+static NUMBER_KEYS: &'static [(KeyCode, char)] = &[
+    (KeyCode::Number0, '0'),
+    (KeyCode::Number1, '1'),
+    (KeyCode::Number2, '2'),
+    (KeyCode::Number3, '3'),
+    (KeyCode::Number4, '4'),
+    (KeyCode::Number5, '5'),
+    (KeyCode::Number6, '6'),
+    (KeyCode::Number7, '7'),
+    (KeyCode::Number8, '8'),
+    (KeyCode::Number9, '9'),
+];
+
+/// The translation of every TCOD key code which does not stand for a
+/// character.
+static DEFAULT_KEY_MAP: &'static [(KeyCode, PistonKey)] = &[
+    (KeyCode::NoKey, PistonKey::Unknown),
+    (KeyCode::Escape, PistonKey::Escape),
+    (KeyCode::Backspace, PistonKey::Backspace),
+    (KeyCode::Tab, PistonKey::Tab),
+    (KeyCode::Enter, PistonKey::Return),
+    // TCOD does not report which Shift key is held, only that one is.
+    (KeyCode::Shift, PistonKey::LShift),
+    (KeyCode::Control, PistonKey::LCtrl),
+    (KeyCode::Alt, PistonKey::LAlt),
+    (KeyCode::Pause, PistonKey::Pause),
+    (KeyCode::CapsLock, PistonKey::CapsLock),
+    (KeyCode::PageUp, PistonKey::PageUp),
+    (KeyCode::PageDown, PistonKey::PageDown),
+    (KeyCode::End, PistonKey::End),
+    (KeyCode::Home, PistonKey::Home),
+    (KeyCode::Up, PistonKey::Up),
+    (KeyCode::Left, PistonKey::Left),
+    (KeyCode::Right, PistonKey::Right),
+    (KeyCode::Down, PistonKey::Down),
+    (KeyCode::PrintScreen, PistonKey::PrintScreen),
+    (KeyCode::Insert, PistonKey::Insert),
+    (KeyCode::Delete, PistonKey::Delete),
+    (KeyCode::LeftWin, PistonKey::LGui),
+    (KeyCode::RightWin, PistonKey::RGui),
+    (KeyCode::Apps, PistonKey::Application),
+    // The numbers on the numeric keypad
+    (KeyCode::NumPad0, PistonKey::NumPad0),
+    (KeyCode::NumPad1, PistonKey::NumPad1),
+    (KeyCode::NumPad2, PistonKey::NumPad2),
+    (KeyCode::NumPad3, PistonKey::NumPad3),
+    (KeyCode::NumPad4, PistonKey::NumPad4),
+    (KeyCode::NumPad5, PistonKey::NumPad5),
+    (KeyCode::NumPad6, PistonKey::NumPad6),
+    (KeyCode::NumPad7, PistonKey::NumPad7),
+    (KeyCode::NumPad8, PistonKey::NumPad8),
+    (KeyCode::NumPad9, PistonKey::NumPad9),
+    (KeyCode::NumPadAdd, PistonKey::NumPadPlus),
+    (KeyCode::NumPadSubtract, PistonKey::NumPadMinus),
+    (KeyCode::NumPadDivide, PistonKey::NumPadDivide),
+    (KeyCode::NumPadMultiply, PistonKey::NumPadMultiply),
+    (KeyCode::NumPadDecimal, PistonKey::NumPadDecimal),
+    (KeyCode::NumPadEnter, PistonKey::NumPadEnter),
+    (KeyCode::F1, PistonKey::F1),
+    (KeyCode::F2, PistonKey::F2),
+    (KeyCode::F3, PistonKey::F3),
+    (KeyCode::F4, PistonKey::F4),
+    (KeyCode::F5, PistonKey::F5),
+    (KeyCode::F6, PistonKey::F6),
+    (KeyCode::F7, PistonKey::F7),
+    (KeyCode::F8, PistonKey::F8),
+    (KeyCode::F9, PistonKey::F9),
+    (KeyCode::F10, PistonKey::F10),
+    (KeyCode::F11, PistonKey::F11),
+    (KeyCode::F12, PistonKey::F12),
+    (KeyCode::NumLock, PistonKey::NumLockClear),
+    (KeyCode::ScrollLock, PistonKey::ScrollLock),
+    (KeyCode::Spacebar, PistonKey::Space),
+];
+
+/// A table translating TCOD keys to piston-input keys.
+///
+/// Keys are first looked up by their TCOD key code, and keys with a code
+/// which does not stand for a character, such as `Char` or `Number0`, are
+/// then looked up by their printable character. Bindings override the
+/// default translation done by `tcod_map_key` for the keys they cover.
+///
+/// A key map can be loaded from a text file with one binding per line, of
+/// either a TCOD key code or a character in single quotes, and the name of
+/// the Piston key it translates to. Lines starting with `#` are comments:
+///
+/// ```text
+/// # Swap Escape and Backspace.
+/// Escape = Backspace
+/// Backspace = Escape
+///
+/// # AZERTY letters, bound to the keys in the same place on a QWERTY layout.
+/// 'a' = Q
+/// 'q' = A
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// # extern crate piston;
+/// # extern crate tcod;
+/// # extern crate tcod_window;
+/// #
+/// use piston::input::Key as PistonKey;
+/// use tcod::input::{Key, KeyCode};
+/// use tcod_window::KeyMap;
+///
+/// # fn main() {
+/// let mut key_map = KeyMap::new();
+/// key_map.set_char('q', PistonKey::A);
+///
+/// let tcod_key = Key {
+///     code: KeyCode::Char,
+///     printable: 'q',
+///     ..Key::default()
+/// };
+///
+/// assert_eq!(key_map.map(tcod_key), PistonKey::A);
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct KeyMap {
+    codes: Vec<(KeyCode, PistonKey)>,
+    chars: HashMap<char, PistonKey>,
+}
+
+impl KeyMap {
+    /// Creates a key map with the default translation of every key.
+    pub fn new() -> Self {
+        KeyMap::default()
+    }
+
+    /// Reads a key map from the file at the given path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file could not be read or is malformed.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = try!(File::open(path));
+        KeyMap::from_reader(BufReader::new(file))
+    }
+
+    /// Reads a key map in the format described above.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key map could not be read or is malformed.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, Error> {
+        let mut key_map = KeyMap::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = try!(line);
+            try!(key_map.parse_binding(line.trim()).map_err(|message| {
+                Error::InvalidKeyMap {
+                    line: i + 1,
+                    message: message,
+                }
+            }));
+        }
+
+        Ok(key_map)
+    }
+
+    /// Binds a TCOD key code to a piston-input key.
+    pub fn set_code(&mut self, code: KeyCode, key: PistonKey) {
+        self.codes.retain(|&(bound, _)| bound != code);
+        self.codes.push((code, key));
+    }
+
+    /// Binds a printable character to a piston-input key.
+    ///
+    /// The letters A to Z are bound regardless of case.
+    pub fn set_char(&mut self, c: char, key: PistonKey) {
+        self.chars.insert(lowercase(c), key);
+    }
+
+    /// Maps a TCOD key to a piston-input key.
+    ///
+    /// Keys pressed along with Ctrl or Alt map to the same key as without
+    /// them, e.g. Ctrl+S maps to `S` rather than to the control character
+    /// TCOD reports for it.
+    ///
+    /// Unless they are bound in this map, Control and Alt keys are reported as
+    /// `RCtrl` or `RAlt` if only the right-hand modifier flag is set.
+    pub fn map(&self, key: TcodKey) -> PistonKey {
+        self.map_with_history(key, None)
+    }
+
+    /// Maps a TCOD key event to a piston-input key, using the modifier flags
+    /// of the previous key event to tell which side's Ctrl or Alt key changed
+    /// state.
+    ///
+    /// When both the left and right keys are involved, the modifier flags of a
+    /// single event are ambiguous, and on release they no longer include the
+    /// key being released at all. Control and Alt keys bound in this map are
+    /// reported as bound, whichever side changed state.
+    pub fn map_event(&self, key: TcodKey, pressed: bool, prev: TcodKey) -> PistonKey {
+        self.map_with_history(key, Some((pressed, prev)))
+    }
+
+    /// Maps a TCOD key to a piston-input key, telling the sides of Ctrl and
+    /// Alt apart from the previous key event and whether this one is a press,
+    /// if known.
+    fn map_with_history(&self, key: TcodKey, history: Option<(bool, TcodKey)>) -> PistonKey {
+        if let Some(&(_, bound)) = self.codes.iter().find(|&&(code, _)| code == key.code) {
+            return bound;
+        }
+        let mapped = match DEFAULT_KEY_MAP.iter().find(|&&(code, _)| code == key.code) {
+            Some(&(_, mapped)) => mapped,
+            None => return self.map_char(base_char(key)),
+        };
+        if mapped != PistonKey::LCtrl && mapped != PistonKey::LAlt {
+            return mapped;
+        }
+
+        let sides = |key: TcodKey| if mapped == PistonKey::LCtrl {
+            (key.left_ctrl, key.right_ctrl)
+        } else {
+            (key.left_alt, key.right_alt)
+        };
+        let (left, right) = sides(key);
+        let is_right = match history {
+            // The right-hand flag is set by its own key's press and cleared
+            // by its release.
+            Some((true, prev)) => right && !sides(prev).1,
+            Some((false, prev)) => sides(prev).1 && !right,
+            None => right && !left,
+        };
+
+        match (mapped, is_right) {
+            (PistonKey::LCtrl, true) => PistonKey::RCtrl,
+            (PistonKey::LAlt, true) => PistonKey::RAlt,
+            _ => mapped,
+        }
+    }
+
+    /// Maps the printable character of a TCOD key to a piston-input key.
+    fn map_char(&self, c: char) -> PistonKey {
+        let c = lowercase(c);
+        match self.chars.get(&c) {
+            Some(&key) => key,
+            None => char_key(c),
+        }
+    }
+
+    /// Parses a single line of a key map file, adding its binding.
+    fn parse_binding(&mut self, line: &str) -> Result<(), String> {
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(());
+        }
+
+        // Piston key names never contain `=`, but a bound character may.
+        let (from, to) = match line.rfind('=') {
+            Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+            None => return Err(format!("expected `=` in {:?}", line)),
+        };
+        let key = try!(piston_key_from_name(to)
+                           .ok_or_else(|| format!("unknown Piston key {:?}", to)));
+
+        let chars: Vec<char> = from.chars().collect();
+        if chars.len() == 3 && chars[0] == '\'' && chars[2] == '\'' {
+            self.set_char(chars[1], key);
+        } else {
+            let code = try!(key_code_from_name(from)
+                                .ok_or_else(|| format!("unknown TCOD key {:?}", from)));
+            self.set_code(code, key);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the character of a TCOD key as it would be typed without Ctrl.
+///
+/// While Ctrl is held, TCOD reports letters as the control characters
+/// `'\x01'` to `'\x1a'`, and some keys without any printable character at
+/// all. The digits among the latter are recovered from the key code. Alt does
+/// not change the printable character.
+fn base_char(key: TcodKey) -> char {
+    let ctrl = key.left_ctrl || key.right_ctrl;
+    match key.printable {
+        c @ '\x01' ... '\x1a' if ctrl => (b'a' + c as u8 - 1) as char,
+        '\0' => {
+            NUMBER_KEYS.iter()
+                       .find(|&&(code, _)| code == key.code)
+                       .map_or('\0', |&(_, digit)| digit)
+        },
+        c => c,
+    }
+}
+
+/// Maps a printable ASCII character to the piston-input key which produces
+/// it.
+///
+/// Characters Piston has no key for, such as `~` or any non-ASCII character,
+/// map to `Unknown`. They still reach the application through the `Text`
+/// event of the key press.
+fn char_key(c: char) -> PistonKey {
+    match c {
+        ' ' => PistonKey::Space,
+        '!' => PistonKey::Exclaim,
+        '"' => PistonKey::Quotedbl,
+        '#' => PistonKey::Hash,
+        '$' => PistonKey::Dollar,
+        '%' => PistonKey::Percent,
+        '&' => PistonKey::Ampersand,
+        '\'' => PistonKey::Quote,
+        '(' => PistonKey::LeftParen,
+        ')' => PistonKey::RightParen,
+        '*' => PistonKey::Asterisk,
+        '+' => PistonKey::Plus,
+        ',' => PistonKey::Comma,
+        '-' => PistonKey::Minus,
+        '.' => PistonKey::Period,
+        '/' => PistonKey::Slash,
+        '0' => PistonKey::D0,
+        '1' => PistonKey::D1,
+        '2' => PistonKey::D2,
+        '3' => PistonKey::D3,
+        '4' => PistonKey::D4,
+        '5' => PistonKey::D5,
+        '6' => PistonKey::D6,
+        '7' => PistonKey::D7,
+        '8' => PistonKey::D8,
+        '9' => PistonKey::D9,
+        ':' => PistonKey::Colon,
+        ';' => PistonKey::Semicolon,
+        '<' => PistonKey::Less,
+        '=' => PistonKey::Equals,
+        '>' => PistonKey::Greater,
+        '?' => PistonKey::Question,
+        '@' => PistonKey::At,
+        '[' => PistonKey::LeftBracket,
+        '\\' => PistonKey::Backslash,
+        ']' => PistonKey::RightBracket,
+        '^' => PistonKey::Caret,
+        '_' => PistonKey::Underscore,
+        '`' => PistonKey::Backquote,
+        // Letters
+        'a' | 'A' => PistonKey::A,
+        'b' | 'B' => PistonKey::B,
+        'c' | 'C' => PistonKey::C,
+        'd' | 'D' => PistonKey::D,
+        'e' | 'E' => PistonKey::E,
+        'f' | 'F' => PistonKey::F,
+        'g' | 'G' => PistonKey::G,
+        'h' | 'H' => PistonKey::H,
+        'i' | 'I' => PistonKey::I,
+        'j' | 'J' => PistonKey::J,
+        'k' | 'K' => PistonKey::K,
+        'l' | 'L' => PistonKey::L,
+        'm' | 'M' => PistonKey::M,
+        'n' | 'N' => PistonKey::N,
+        'o' | 'O' => PistonKey::O,
+        'p' | 'P' => PistonKey::P,
+        'q' | 'Q' => PistonKey::Q,
+        'r' | 'R' => PistonKey::R,
+        's' | 'S' => PistonKey::S,
+        't' | 'T' => PistonKey::T,
+        'u' | 'U' => PistonKey::U,
+        'v' | 'V' => PistonKey::V,
+        'w' | 'W' => PistonKey::W,
+        'x' | 'X' => PistonKey::X,
+        'y' | 'Y' => PistonKey::Y,
+        'z' | 'Z' => PistonKey::Z,
+        _ => PistonKey::Unknown,
+    }
+}
+
+/// Maps a piston-input key to a TCOD key which produces it.
+///
+/// This is the inverse of `tcod_map_key`: for every key `k` that
+/// `tcod_map_key` can return, other than `Unknown`, `piston_map_key(k)` is a
+/// TCOD key which maps back to `k`. `None` is returned for `Unknown` and for
+/// keys TCOD never reports.
+///
+/// Since TCOD does not report which Shift key is held, `LShift` and `RShift`
+/// both map to `KeyCode::Shift`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate piston;
+/// # extern crate tcod;
+/// # extern crate tcod_window;
+/// #
+/// use piston::input::Key as PistonKey;
+/// use tcod::input::KeyCode;
+/// use tcod_window::{piston_map_key, tcod_map_key};
+///
+/// # fn main() {
+/// let tcod_key = piston_map_key(PistonKey::D1).unwrap();
+/// assert_eq!(tcod_key.code, KeyCode::Number1);
+/// assert_eq!(tcod_key.printable, '1');
+/// assert_eq!(tcod_map_key(tcod_key), PistonKey::D1);
+/// # }
+/// ```
+pub fn piston_map_key(key: PistonKey) -> Option<TcodKey> {
+    // The tables only hold the left-hand modifiers, the side is given by the
+    // modifier flags below.
+    let left_key = match key {
+        PistonKey::Unknown => return None,
+        PistonKey::RShift => PistonKey::LShift,
+        PistonKey::RCtrl => PistonKey::LCtrl,
+        PistonKey::RAlt => PistonKey::LAlt,
+        key => key,
+    };
+
+    // Letters are typed as lowercase characters.
+    let printable = (0x20u8..0x7f)
+                        .map(|c| c as char)
+                        .filter(|&c| lowercase(c) == c)
+                        .find(|&c| char_key(c) == left_key);
+    let code = DEFAULT_KEY_MAP.iter()
+                              .find(|&&(_, mapped)| mapped == left_key)
+                              .map(|&(code, _)| code)
+                              .or_else(|| {
+                                  printable.map(|c| {
+                                      NUMBER_KEYS.iter()
+                                                 .find(|&&(_, digit)| digit == c)
+                                                 .map_or(KeyCode::Char, |&(code, _)| code)
+                                  })
+                              });
+
+    code.map(|code| {
+        TcodKey {
+            code: code,
+            printable: printable.unwrap_or('\0'),
+            left_ctrl: key == PistonKey::LCtrl,
+            right_ctrl: key == PistonKey::RCtrl,
+            left_alt: key == PistonKey::LAlt,
+            right_alt: key == PistonKey::RAlt,
+            ..TcodKey::default()
+        }
+    })
+}
+
+/// Returns the piston-input key with the given name, as formatted by `Debug`.
+fn piston_key_from_name(name: &str) -> Option<PistonKey> {
+    // Piston keys share their codes with SDL keycodes, which are either
+    // ASCII or lie in a single block of scancode-based values.
+    (0u32..0x80)
+        .chain(0x40000039..0x40000120)
+        .map(PistonKey::from)
+        .find(|key| format!("{:?}", key) == name)
+}
+
+/// Lowercases the letters A to Z, leaving every other character alone.
+fn lowercase(c: char) -> char {
+    match c {
+        'A' ... 'Z' => c.to_lowercase().next().unwrap(),
+        c => c,
+    }
+}
@@ -18,7 +18,7 @@
 //! The following example shows a quick example of the basic functionality of
 //! `TcodWindow`.
 //!
-//! ```
+//! ```no_run
 //! extern crate piston;
 //! extern crate tcod_window;
 //!
@@ -37,6 +37,9 @@
 //!     );
 //! }
 //! ```
+//!
+//! Where no display is available, such as in tests, `TcodWindow::headless`
+//! creates a window which draws into an offscreen console instead.
 
 extern crate input;
 #[macro_use]
@@ -44,35 +47,112 @@ extern crate tcod;
 extern crate window;
 
 use std::cell::RefCell;
+use std::cmp;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::mem;
+use std::path::Path;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
-use input::{Input, MouseButton};
+use builder::FontConfig;
+use input::{Input, Motion, MouseButton};
 use input::keyboard::Key as PistonKey;
-use tcod::input::{Key as TcodKey, KeyCode, Mouse};
-use tcod::Console;
-use tcod::console::Root;
+use record::{Recorder, Replay};
+use tcod::input::{Event, EventFlags, Key as TcodKey, KeyCode, Mouse};
+use tcod::console::{self, FontLayout, Offscreen, Renderer, Root};
 use window::{AdvancedWindow, BuildFromWindowSettings, Size, Window, WindowSettings};
 
+pub use backend::{Backend, Headless};
+pub use builder::TcodWindowBuilder;
+pub use error::Error;
+pub use event_source::{EventSource, TcodEventSource};
+pub use keymap::{KeyMap, piston_map_key};
+pub use state::InputState;
+
+mod backend;
+mod builder;
+mod error;
+mod event_source;
+mod keymap;
+pub mod record;
+mod state;
+
+/// The units in which a `TcodWindow` reports mouse positions and motion.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CoordinateMode {
+    /// Window pixels, as reported by `Mouse::x` and `Mouse::y`.
+    Pixels,
+    /// Whole console cells, as reported by `Mouse::cx` and `Mouse::cy`.
+    Cells,
+    /// Console cells, including the fractional position within the cell.
+    FractionalCells,
+}
+
+impl CoordinateMode {
+    /// Returns the position of the mouse in this coordinate mode, given the
+    /// size of a console cell in pixels.
+    fn position(self, state: &Mouse, char_size: (i32, i32)) -> (f64, f64) {
+        match self {
+            CoordinateMode::Pixels => (state.x as f64, state.y as f64),
+            CoordinateMode::Cells => (state.cx as f64, state.cy as f64),
+            CoordinateMode::FractionalCells => {
+                (state.x as f64 / char_size.0 as f64, state.y as f64 / char_size.1 as f64)
+            },
+        }
+    }
+}
+
+impl Default for CoordinateMode {
+    fn default() -> Self {
+        CoordinateMode::Pixels
+    }
+}
+
 /// A window implemented by a TCOD back-end.
-pub struct TcodWindow {
-    /// TCOD `Root` window used for rendering.
-    pub window: Rc<RefCell<Root>>,
+///
+/// By default the window is shown through a TCOD `Root` console. A
+/// `TcodWindow<Headless>` draws into an offscreen console instead, and can be
+/// used without a display.
+pub struct TcodWindow<B: Backend = Root> {
+    /// TCOD console used for rendering.
+    pub window: Rc<RefCell<B>>,
     title: String,
     should_close: bool,
-    mouse_relative: Option<(f64, f64)>,
+    source: Box<EventSource>,
+    recorder: Option<Recorder<Box<EventSource>, Box<Write>>>,
+    events: VecDeque<Input>,
+    key_state_prev: TcodKey,
     mouse_state_prev: Mouse,
+    mouse_state_pending: Option<Mouse>,
     exit_on_esc: bool,
+    coordinate_mode: CoordinateMode,
+    capture_cursor: bool,
+    fullscreen_toggle: bool,
+    input_state: InputState,
+    focused: bool,
+    font: Option<FontConfig>,
+    key_map: KeyMap,
 }
 
+/// A `TcodWindow` which draws into an offscreen console, without a display.
+pub type HeadlessWindow = TcodWindow<Headless>;
+
 impl TcodWindow {
     /// Create a new game window from the provided `WindowSettings`.
     ///
-    /// Internally, it initializes a new TCOD `Root` with the size and title
-    /// specified in the `WindowSettings`.
+    /// Internally, it initializes a new TCOD `Root` with the size, title and
+    /// fullscreen mode specified in the `WindowSettings`. Use a
+    /// `TcodWindowBuilder` to configure TCOD-specific options.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the window cannot be created. Use `TcodWindowBuilder::build`
+    /// to handle the error instead.
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
     /// # extern crate piston;
     /// # extern crate tcod_window;
     /// #
@@ -92,22 +172,20 @@ impl TcodWindow {
     /// # }
     /// ```
     pub fn new(settings: WindowSettings) -> Self {
-        let console = Root::initializer()
-                          .size(settings.get_size().width as i32,
-                                settings.get_size().height as i32)
-                          .title(settings.get_title())
-                          .init();
-        let console = Rc::new(RefCell::new(console));
-
-        Self::with_console(console, settings)
+        TcodWindowBuilder::new(settings)
+            .build()
+            .unwrap_or_else(|err| panic!("Failed to create TCOD window: {}", err))
     }
 
     /// Create a new game window from an existing TCOD `Root` console wrapped as
     /// an `Rc<RefCell<Root>>`.
     ///
+    /// The font the console was initialized with cannot be queried from TCOD,
+    /// so `set_size` and `set_font` are unavailable on such a window.
+    ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
     /// # extern crate piston;
     /// # extern crate tcod;
     /// # extern crate tcod_window;
@@ -138,68 +216,572 @@ impl TcodWindow {
     /// # }
     /// ```
     pub fn with_console(console: Rc<RefCell<Root>>, settings: WindowSettings) -> Self {
+        Self::with_backend(console, settings, TcodEventSource)
+    }
+
+    /// Returns the renderer TCOD is using to draw the console.
+    ///
+    /// This may differ from the one requested through `TcodWindowBuilder` if
+    /// it was unavailable.
+    pub fn renderer(&self) -> Renderer {
+        tcod::system::get_renderer()
+    }
+
+    /// Resizes the console to the given size, in cells.
+    ///
+    /// TCOD cannot resize its root console in place, so it is re-initialized
+    /// with the same title, font, renderer and fullscreen mode. The contents
+    /// of the console are kept where they still fit. A resize event is
+    /// emitted if the size changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either dimension is zero or too large, or if the
+    /// window was created with `with_console`.
+    pub fn set_size(&mut self, size: Size) -> Result<(), Error> {
+        try!(builder::check_size(size));
+        let font = try!(self.font.take().ok_or(Error::ExternalConsole));
+        self.reinit_root(size, &font);
+        self.font = Some(font);
+        Ok(())
+    }
+
+    /// Changes the font the console is rendered with.
+    ///
+    /// The console is re-initialized as with `set_size`, keeping its contents.
+    /// Its size in cells stays the same, but a resize event is emitted if the
+    /// size of the glyphs, and with it `draw_size`, changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the font file does not exist, or if the window was
+    /// created with `with_console`.
+    pub fn set_font<P: AsRef<Path>>(&mut self, path: P, layout: FontLayout) -> Result<(), Error> {
+        let path = path.as_ref().to_owned();
+        if !path.is_file() {
+            return Err(Error::FontNotFound(path));
+        }
+
+        let mut font = try!(self.font.take().ok_or(Error::ExternalConsole));
+        font.font = Some((path, layout));
+        let size = self.size();
+        self.reinit_root(size, &font);
+        self.font = Some(font);
+        Ok(())
+    }
+
+    /// Re-initializes the TCOD `Root` console at the given size and with the
+    /// given font, copying over the contents of the old one.
+    fn reinit_root(&mut self, size: Size, font: &FontConfig) {
+        let (old_size, old_draw_size) = (self.size(), self.draw_size());
+        let (width, height) = self.window.borrow().console_size();
+        let mut contents = Offscreen::new(width, height);
+        console::blit(&*self.window.borrow(),
+                      (0, 0),
+                      (width, height),
+                      &mut contents,
+                      (0, 0),
+                      1.0,
+                      1.0);
+
+        let fullscreen = self.is_fullscreen();
+        let renderer = self.renderer();
+        let root = builder::init_root(size, &self.title, fullscreen, Some(renderer), font);
+        // `TCOD_console_init_root` has just pointed TCOD's global root console
+        // at the new console. A `Root` has no console of its own: the only way
+        // for it to release anything is `TCOD_console_delete(NULL)`, which
+        // deletes whatever the global root is and shuts SDL down. Dropping the
+        // old `Root` could therefore only tear down the new console, and if
+        // `Root` has no destructor, forgetting it is the same as dropping it.
+        mem::forget(mem::replace(&mut *self.window.borrow_mut(), root));
+
+        let kept = (cmp::min(width, size.width as i32), cmp::min(height, size.height as i32));
+        console::blit(&contents,
+                      (0, 0),
+                      kept,
+                      &mut *self.window.borrow_mut(),
+                      (0, 0),
+                      1.0,
+                      1.0);
+        self.queue_resize(old_size, old_draw_size);
+    }
+}
+
+impl TcodWindow<Headless> {
+    /// Create a new game window which draws into an offscreen console of the
+    /// size specified in the `WindowSettings`, without opening a window.
+    ///
+    /// A headless window receives no events from TCOD; use `set_event_source`
+    /// to feed it events instead, e.g. from a `VecDeque` of scripted events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate piston;
+    /// # extern crate tcod;
+    /// # extern crate tcod_window;
+    /// #
+    /// use std::collections::VecDeque;
+    ///
+    /// use piston::input::{Button, Input, Key};
+    /// use piston::window::{Size, Window, WindowSettings};
+    /// use tcod::input::{self, Event, KeyCode};
+    /// use tcod_window::TcodWindow;
+    ///
+    /// # fn main() {
+    /// let mut window = TcodWindow::headless(
+    ///     WindowSettings::new(
+    ///         "My Application".to_owned(),
+    ///         Size {
+    ///             width: 100,
+    ///             height: 100,
+    ///         }
+    ///     )
+    /// );
+    ///
+    /// let mut events = VecDeque::new();
+    /// events.push_back((input::KEY_PRESS,
+    ///                   Event::Key(input::Key {
+    ///                       code: KeyCode::Up,
+    ///                       ..input::Key::default()
+    ///                   })));
+    /// window.set_event_source(events);
+    ///
+    /// assert_eq!(window.poll_event(), Some(Input::Press(Button::Keyboard(Key::Up))));
+    /// # }
+    /// ```
+    pub fn headless(settings: WindowSettings) -> Self {
+        let size = settings.get_size();
+        let console = Headless::new(size.width as i32, size.height as i32);
+
+        Self::with_backend(Rc::new(RefCell::new(console)),
+                           settings,
+                           VecDeque::<(EventFlags, Event)>::new())
+    }
+
+    /// Resizes the offscreen console to the given size, in cells.
+    ///
+    /// The contents of the console are kept where they still fit. A resize
+    /// event is emitted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either dimension is zero or too large.
+    pub fn set_size(&mut self, size: Size) -> Result<(), Error> {
+        try!(builder::check_size(size));
+        let (old_size, old_draw_size) = (self.size(), self.draw_size());
+        self.window.borrow_mut().resize(size.width as i32, size.height as i32);
+        self.queue_resize(old_size, old_draw_size);
+        Ok(())
+    }
+}
+
+impl<B: Backend> TcodWindow<B> {
+    /// Create a new game window from an existing backend console wrapped as an
+    /// `Rc<RefCell<B>>`, translating the events read from `source`.
+    pub fn with_backend<S: EventSource + 'static>(console: Rc<RefCell<B>>,
+                                                  settings: WindowSettings,
+                                                  source: S)
+                                                  -> Self {
         TcodWindow {
             window: console,
             title: settings.get_title(),
             should_close: false,
-            mouse_relative: None,
+            source: Box::new(source),
+            recorder: None,
+            events: VecDeque::new(),
+            key_state_prev: TcodKey::default(),
             mouse_state_prev: Mouse::default(),
+            mouse_state_pending: None,
             exit_on_esc: settings.get_exit_on_esc(),
+            coordinate_mode: CoordinateMode::default(),
+            capture_cursor: false,
+            fullscreen_toggle: false,
+            input_state: InputState::default(),
+            focused: true,
+            font: None,
+            key_map: KeyMap::default(),
+        }
+    }
+
+    /// Returns the size of a single console cell, in pixels.
+    ///
+    /// This is the size of a glyph of the font the console is rendered with.
+    pub fn glyph_size(&self) -> Size {
+        let (width, height) = self.window.borrow().char_size();
+        Size {
+            width: width as u32,
+            height: height as u32,
+        }
+    }
+
+    /// Sets the source of the raw TCOD events translated by `poll_event`.
+    ///
+    /// Windows created with `new` or `with_console` read events from TCOD
+    /// through a `TcodEventSource`.
+    ///
+    /// A recording in progress carries on with the events of the new source.
+    pub fn set_event_source<S: EventSource + 'static>(&mut self, source: S) {
+        let source: Box<EventSource> = Box::new(source);
+        match self.recorder {
+            Some(ref mut recorder) => *recorder.source_mut() = source,
+            None => self.source = source,
+        }
+    }
+
+    /// Starts recording every raw TCOD event received from the current event
+    /// source to `writer`, stopping any recording already in progress.
+    ///
+    /// See the `record` module for details of the format. Only the events of
+    /// the event source are recorded: focus changes and resizes come from the
+    /// backend, so a replay does not reproduce them, nor the release of held
+    /// keys and buttons when focus is lost.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the recording header could not be written, or if
+    /// the recording already in progress failed, as with `stop_recording`. No
+    /// recording is in progress afterwards.
+    pub fn record<W: Write + 'static>(&mut self, writer: W) -> io::Result<()> {
+        if let Some(err) = self.stop_recording() {
+            return Err(err);
+        }
+
+        let empty = Box::new(VecDeque::<(EventFlags, Event)>::new());
+        let mut recorder = try!(Recorder::new(empty as Box<EventSource>,
+                                              Box::new(writer) as Box<Write>));
+        mem::swap(recorder.source_mut(), &mut self.source);
+        self.recorder = Some(recorder);
+        Ok(())
+    }
+
+    /// Stops the recording in progress, if any, flushing its writer.
+    ///
+    /// Returns the first error encountered while writing the recording. Events
+    /// keep being passed through after an error, but are no longer recorded.
+    pub fn stop_recording(&mut self) -> Option<io::Error> {
+        let recorder = match self.recorder.take() {
+            Some(recorder) => recorder,
+            None => return None,
+        };
+
+        let (source, error) = recorder.finish();
+        self.source = source;
+        error
+    }
+
+    /// Returns the event source events are read from, recording them if a
+    /// recording is in progress.
+    fn active_source(&mut self) -> &mut EventSource {
+        match self.recorder {
+            Some(ref mut recorder) => recorder,
+            None => &mut *self.source,
+        }
+    }
+
+    /// Replaces the event source with a replay of the recording read from
+    /// `reader`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the recording could not be read.
+    pub fn replay<R: BufRead>(&mut self, reader: R) -> io::Result<()> {
+        self.set_event_source(try!(Replay::from_reader(reader)));
+        Ok(())
+    }
+
+    /// Returns whether the window is in fullscreen mode.
+    pub fn is_fullscreen(&self) -> bool {
+        self.window.borrow().is_fullscreen()
+    }
+
+    /// Switches the window in or out of fullscreen mode.
+    ///
+    /// TCOD keeps the size of the console in cells and centers it on the
+    /// screen, so a resize event is emitted if `draw_size`, which includes the
+    /// borders around the console, changes.
+    pub fn set_fullscreen(&mut self, value: bool) {
+        if value == self.is_fullscreen() {
+            return;
+        }
+
+        let (size, draw_size) = (self.size(), self.draw_size());
+        self.window.borrow_mut().set_fullscreen(value);
+        self.queue_resize(size, draw_size);
+    }
+
+    /// Returns the key map used to translate TCOD keys.
+    pub fn key_map(&self) -> &KeyMap {
+        &self.key_map
+    }
+
+    /// Sets the key map used to translate TCOD keys.
+    pub fn set_key_map(&mut self, value: KeyMap) {
+        self.key_map = value;
+    }
+
+    /// Returns whether pressing Alt+Enter toggles fullscreen mode.
+    pub fn get_fullscreen_toggle(&self) -> bool {
+        self.fullscreen_toggle
+    }
+
+    /// Sets whether pressing Alt+Enter toggles fullscreen mode.
+    ///
+    /// When enabled, the Enter key events of the shortcut are not reported.
+    /// Defaults to `false`.
+    pub fn set_fullscreen_toggle(&mut self, value: bool) {
+        self.fullscreen_toggle = value;
+    }
+
+    /// Returns the keys and mouse buttons held down, and the position of the
+    /// mouse, as of the last event returned by `poll_event`.
+    ///
+    /// Held keys and buttons are forgotten when the window loses focus, since
+    /// their release may never be reported.
+    pub fn input_state(&self) -> &InputState {
+        &self.input_state
+    }
+
+    /// Returns the units in which mouse positions and motion are reported.
+    pub fn coordinate_mode(&self) -> CoordinateMode {
+        self.coordinate_mode
+    }
+
+    /// Sets the units in which mouse positions and motion are reported.
+    ///
+    /// Defaults to `CoordinateMode::Pixels`.
+    pub fn set_coordinate_mode(&mut self, value: CoordinateMode) {
+        self.coordinate_mode = value;
+    }
+
+    /// Blocks until an event is available and returns it.
+    ///
+    /// Returns `None` once the window should close, or if the event source
+    /// cannot produce any more events.
+    ///
+    /// Newer versions of `pistoncore-window` have a `Window::wait_event` method
+    /// which this mirrors. Implementing it is left to the upgrade to those
+    /// versions; see the README.
+    pub fn wait_event(&mut self) -> Option<Input> {
+        self.wait(None)
+    }
+
+    /// Blocks until an event is available or `timeout` has elapsed, and
+    /// returns the event, if any.
+    ///
+    /// Returns `None` early if the window should close.
+    pub fn wait_event_timeout(&mut self, timeout: Duration) -> Option<Input> {
+        self.wait(Some(timeout))
+    }
+
+    fn wait(&mut self, timeout: Option<Duration>) -> Option<Input> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        loop {
+            if let Some(input) = self.poll_event() {
+                return Some(input);
+            }
+            if self.should_close {
+                return None;
+            }
+
+            let remaining = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return None;
+                    }
+                    Some(deadline - now)
+                },
+                None => None,
+            };
+            match self.active_source().wait_event(remaining) {
+                Some((flags, event)) => self.queue_event(flags, event),
+                // Without a timeout, the wait only ends empty-handed if the
+                // window was closed or the source has run out of events.
+                None if remaining.is_none() => {
+                    if self.window.borrow().window_closed() {
+                        self.should_close = true;
+                    }
+                    return None;
+                },
+                None => {},
+            }
         }
     }
 
     fn poll_event(&mut self) -> Option<Input> {
-        use input::{Button, Motion};
-        use input::Input::{Move, Press, Release};
-        use tcod::input::{ANY, KEY_PRESS, KEY_RELEASE, MOUSE_MOVE, MOUSE_PRESS, MOUSE_RELEASE,
-                          check_for_event};
-        use tcod::input::Event::{Key, Mouse};
+        if self.window.borrow().window_closed() {
+            self.should_close = true;
+            return None;
+        }
+
+        // TCOD's focus only follows the mouse pointer, while keyboard focus and
+        // minimization both show up as the window being active.
+        let focused = self.window.borrow().is_active();
+        if focused != self.focused {
+            self.focused = focused;
+            self.events.push_back(Input::Focus(focused));
+        }
+
+        // A single TCOD event may translate to several Piston events (or to
+        // none at all), so keep pulling TCOD events until something has been
+        // queued or TCOD runs dry.
+        while self.events.is_empty() {
+            match self.active_source().next_event() {
+                Some((flags, event)) => self.queue_event(flags, event),
+                None => return None,
+            }
+
+            if self.should_close {
+                return None;
+            }
+        }
+
+        // The input state follows the events as they are returned, not as
+        // they are queued.
+        let input = self.events.pop_front();
+        if let Some(ref input) = input {
+            if let Some(mouse_state) = self.mouse_state_pending.take() {
+                self.input_state.update_mouse(&mouse_state);
+            }
+            self.input_state.update(input);
+        }
+        input
+    }
+
+    /// Maps a TCOD key event to a piston-input key, remembering its modifier
+    /// flags for the next key event.
+    fn map_key(&mut self, key_state: TcodKey, pressed: bool) -> PistonKey {
+        let prev = mem::replace(&mut self.key_state_prev, key_state);
+        self.key_map.map_event(key_state, pressed, prev)
+    }
+
+    /// Appends a resize event for the current size of the window to the
+    /// internal event queue, if either its size in cells or its size in
+    /// pixels differs from `old_size` or `old_draw_size`.
+    fn queue_resize(&mut self, old_size: Size, old_draw_size: Size) {
+        let size = self.size();
+        let draw_size = self.draw_size();
+        if (size.width, size.height) != (old_size.width, old_size.height) ||
+           (draw_size.width, draw_size.height) != (old_draw_size.width, old_draw_size.height) {
+            self.events.push_back(Input::Resize(size.width, size.height));
+        }
+    }
 
-        if let Some((x, y)) = self.mouse_relative {
-            self.mouse_relative = None;
-            return Some(Move(Motion::MouseRelative(x, y)));
+    /// Translates a TCOD event into zero or more Piston events, appending them
+    /// to the internal event queue.
+    fn queue_event(&mut self, flags: EventFlags, event: Event) {
+        use input::Button;
+        use input::Input::{Press, Release, Text};
+        use tcod::input::{KEY_PRESS, KEY_RELEASE};
+
+        if let Event::Key(key_state) = event {
+            let alt = key_state.left_alt || key_state.right_alt;
+            if self.fullscreen_toggle && alt && key_state.code == KeyCode::Enter {
+                if flags == KEY_PRESS {
+                    let fullscreen = self.is_fullscreen();
+                    self.set_fullscreen(!fullscreen);
+                }
+                return;
+            }
         }
 
-        match check_for_event(ANY) {
-            Some((KEY_PRESS, Key(ref key_state))) => {
+        match (flags, event) {
+            (KEY_PRESS, Event::Key(key_state)) => {
                 if self.exit_on_esc && key_state.code == KeyCode::Escape {
                     self.should_close = true;
-                    None
-                } else {
-                    Some(Press(Button::Keyboard(tcod_map_key(*key_state))))
+                    return;
+                }
+                let key = self.map_key(key_state, true);
+                self.events.push_back(Press(Button::Keyboard(key)));
+                if let Some(text) = tcod_map_text(key_state) {
+                    self.events.push_back(Text(text));
                 }
             },
-            Some((KEY_RELEASE, Key(ref key_state))) => {
-                Some(Release(Button::Keyboard(tcod_map_key(*key_state))))
-            },
-            Some((MOUSE_PRESS, Mouse(ref mouse_state))) => {
-                let button = tcod_map_mouse(self.mouse_state_prev, mouse_state);
-                self.mouse_state_prev = *mouse_state;
-                Some(Press(Button::Mouse(button)))
-            },
-            Some((MOUSE_RELEASE, Mouse(ref mouse_state))) => {
-                let button = tcod_map_mouse(self.mouse_state_prev, mouse_state);
-                self.mouse_state_prev = *mouse_state;
-                Some(Release(Button::Mouse(button)))
-            },
-            Some((MOUSE_MOVE, Mouse(ref mouse_state))) => {
-                self.mouse_relative = Some(((mouse_state.x - self.mouse_state_prev.x) as f64,
-                                            (mouse_state.y - self.mouse_state_prev.y) as f64));
-                self.mouse_state_prev = *mouse_state;
-                Some(Move(Motion::MouseCursor(mouse_state.x as f64, mouse_state.y as f64)))
+            (KEY_RELEASE, Event::Key(key_state)) => {
+                let key = self.map_key(key_state, false);
+                self.events.push_back(Release(Button::Keyboard(key)));
             },
-            _ => None,
+            (_, Event::Mouse(mouse_state)) => self.queue_mouse_event(flags, mouse_state),
+            _ => {},
+        }
+    }
+
+    /// Returns the mouse state of a cursor resting at the center of the window.
+    fn cursor_center(&self) -> Mouse {
+        let size = self.size();
+        let draw_size = self.draw_size();
+        let glyph_size = self.glyph_size();
+        let x = draw_size.width / 2;
+        let y = draw_size.height / 2;
+        // In fullscreen mode the console is centered within the window.
+        let offset_x = (draw_size.width - size.width * glyph_size.width) / 2;
+        let offset_y = (draw_size.height - size.height * glyph_size.height) / 2;
+
+        Mouse {
+            x: x as _,
+            y: y as _,
+            cx: ((x - offset_x) / glyph_size.width) as _,
+            cy: ((y - offset_y) / glyph_size.height) as _,
+            ..Mouse::default()
+        }
+    }
+
+    /// Translates a TCOD mouse event into zero or more Piston events, appending
+    /// them to the internal event queue.
+    fn queue_mouse_event(&mut self, flags: EventFlags, mouse_state: Mouse) {
+        use input::Input::Move;
+        use tcod::input::MOUSE_MOVE;
+
+        let prev = self.mouse_state_prev;
+        self.mouse_state_prev = mouse_state;
+        self.mouse_state_pending = Some(mouse_state);
+
+        if flags == MOUSE_MOVE {
+            let char_size = self.window.borrow().char_size();
+            let (x, y) = self.coordinate_mode.position(&mouse_state, char_size);
+
+            if self.capture_cursor {
+                // The cursor is warped back to the center after every move, so
+                // motion is measured from there rather than from `prev`.
+                let center = self.cursor_center();
+                let (center_x, center_y) = self.coordinate_mode.position(&center, char_size);
+                if (x, y) != (center_x, center_y) {
+                    self.events
+                        .push_back(Move(Motion::MouseRelative(x - center_x, y - center_y)));
+                    self.window.borrow_mut().move_cursor(center.x as i32, center.y as i32);
+                }
+            } else {
+                let (prev_x, prev_y) = self.coordinate_mode.position(&prev, char_size);
+                self.events.push_back(Move(Motion::MouseCursor(x, y)));
+                if (x, y) != (prev_x, prev_y) {
+                    self.events.push_back(Move(Motion::MouseRelative(x - prev_x, y - prev_y)));
+                }
+            }
+        }
+
+        self.events.extend(tcod_map_mouse_buttons(prev, &mouse_state));
+
+        if let Some(scroll) = tcod_map_scroll(&mouse_state) {
+            self.events.push_back(Move(scroll));
         }
     }
 }
 
 impl BuildFromWindowSettings for TcodWindow {
     fn build_from_window_settings(settings: WindowSettings) -> Result<Self, String> {
-        Ok(TcodWindow::new(settings))
+        TcodWindowBuilder::new(settings).build().map_err(|err| err.to_string())
+    }
+}
+
+impl BuildFromWindowSettings for TcodWindow<Headless> {
+    fn build_from_window_settings(settings: WindowSettings) -> Result<Self, String> {
+        Ok(TcodWindow::headless(settings))
     }
 }
 
-impl Window for TcodWindow {
+impl<B: Backend> Window for TcodWindow<B> {
     type Event = Input;
 
     fn should_close(&self) -> bool {
@@ -212,30 +794,30 @@ impl Window for TcodWindow {
         self.window.borrow_mut().flush();
     }
     fn size(&self) -> Size {
-        let window = self.window.borrow();
+        let (width, height) = self.window.borrow().console_size();
         Size {
-            width: window.width() as u32,
-            height: window.height() as u32,
+            width: width as u32,
+            height: height as u32,
         }
     }
     fn poll_event(&mut self) -> Option<Input> {
         self.poll_event()
     }
     fn draw_size(&self) -> Size {
-        let window = self.window.borrow();
+        let (width, height) = self.window.borrow().window_size();
         Size {
-            width: window.width() as u32,
-            height: window.height() as u32,
+            width: width as u32,
+            height: height as u32,
         }
     }
 }
 
-impl AdvancedWindow for TcodWindow {
+impl<B: Backend> AdvancedWindow for TcodWindow<B> {
     fn get_title(&self) -> String {
         self.title.clone()
     }
     fn set_title(&mut self, value: String) {
-        self.window.borrow_mut().set_window_title(&value);
+        self.window.borrow_mut().set_title(&value);
         self.title = value
     }
     fn get_exit_on_esc(&self) -> bool {
@@ -244,11 +826,20 @@ impl AdvancedWindow for TcodWindow {
     fn set_exit_on_esc(&mut self, value: bool) {
         self.exit_on_esc = value;
     }
-    fn set_capture_cursor(&mut self, _value: bool) {}
+    fn set_capture_cursor(&mut self, value: bool) {
+        self.window.borrow_mut().show_cursor(!value);
+        if value {
+            let center = self.cursor_center();
+            self.window.borrow_mut().move_cursor(center.x as i32, center.y as i32);
+        }
+        self.capture_cursor = value;
+    }
 }
 
 /// Maps a TCOD key to a piston-input key.
 ///
+/// This is the default translation of a `KeyMap`.
+///
 /// # Examples
 ///
 /// ```
@@ -271,78 +862,55 @@ impl AdvancedWindow for TcodWindow {
 /// # }
 /// ```
 pub fn tcod_map_key(key: TcodKey) -> PistonKey {
-    match key.code {
-        KeyCode::NoKey => PistonKey::Unknown,
-        KeyCode::Escape => PistonKey::Escape,
-        KeyCode::Backspace => PistonKey::Backspace,
-        KeyCode::Tab => PistonKey::Tab,
-        KeyCode::Enter => PistonKey::Return,
-        KeyCode::Shift => PistonKey::LShift,
-        KeyCode::Control => PistonKey::LCtrl,
-        KeyCode::Alt => PistonKey::LAlt,
-        KeyCode::Pause => PistonKey::Pause,
-        KeyCode::CapsLock => PistonKey::CapsLock,
-        KeyCode::PageUp => PistonKey::PageUp,
-        KeyCode::PageDown => PistonKey::PageDown,
-        KeyCode::End => PistonKey::End,
-        KeyCode::Home => PistonKey::Home,
-        KeyCode::Up => PistonKey::Up,
-        KeyCode::Left => PistonKey::Left,
-        KeyCode::Right => PistonKey::Right,
-        KeyCode::Down => PistonKey::Down,
-        KeyCode::PrintScreen => PistonKey::PrintScreen,
-        KeyCode::Insert => PistonKey::Insert,
-        KeyCode::Delete => PistonKey::Delete,
-        KeyCode::LeftWin => PistonKey::LGui,
-        KeyCode::RightWin => PistonKey::RGui,
-        KeyCode::Apps => PistonKey::Application,
-        // The numbers on the numeric keypad
-        KeyCode::NumPad0 => PistonKey::NumPad0,
-        KeyCode::NumPad1 => PistonKey::NumPad1,
-        KeyCode::NumPad2 => PistonKey::NumPad2,
-        KeyCode::NumPad3 => PistonKey::NumPad3,
-        KeyCode::NumPad4 => PistonKey::NumPad4,
-        KeyCode::NumPad5 => PistonKey::NumPad5,
-        KeyCode::NumPad6 => PistonKey::NumPad6,
-        KeyCode::NumPad7 => PistonKey::NumPad7,
-        KeyCode::NumPad8 => PistonKey::NumPad8,
-        KeyCode::NumPad9 => PistonKey::NumPad9,
-        KeyCode::NumPadAdd => PistonKey::NumPadPlus,
-        KeyCode::NumPadSubtract => PistonKey::NumPadMinus,
-        KeyCode::NumPadDivide => PistonKey::NumPadDivide,
-        KeyCode::NumPadMultiply => PistonKey::NumPadMultiply,
-        KeyCode::NumPadDecimal => PistonKey::NumPadDecimal,
-        KeyCode::NumPadEnter => PistonKey::NumPadEnter,
-        KeyCode::F1 => PistonKey::F1,
-        KeyCode::F2 => PistonKey::F2,
-        KeyCode::F3 => PistonKey::F3,
-        KeyCode::F4 => PistonKey::F4,
-        KeyCode::F5 => PistonKey::F5,
-        KeyCode::F6 => PistonKey::F6,
-        KeyCode::F7 => PistonKey::F7,
-        KeyCode::F8 => PistonKey::F8,
-        KeyCode::F9 => PistonKey::F9,
-        KeyCode::F10 => PistonKey::F10,
-        KeyCode::F11 => PistonKey::F11,
-        KeyCode::F12 => PistonKey::F12,
-        KeyCode::NumLock => PistonKey::NumLockClear,
-        KeyCode::ScrollLock => PistonKey::ScrollLock,
-        KeyCode::Spacebar => PistonKey::Space,
-        // Characters
-        _ => {
-            let key_as_u32 = match key.printable {
-                // We need to convert the uppercase characters to be lowercase.
-                key @ 'A' ... 'Z' => key.to_lowercase().next().unwrap() as u32,
-                key => key as u32,
-            };
+    KeyMap::new().map(key)
+}
 
-            key_as_u32.into()
-        },
+/// Maps a TCOD key press to the text it produces, if any.
+///
+/// The text is the printable character reported by TCOD, which is already
+/// cased according to the state of the Shift and Caps Lock keys. Control
+/// characters do not produce any text.
+///
+/// Neither do Ctrl and Alt chords, which TCOD reports as a control character
+/// or as the plain letter or digit of the key. Other characters typed while
+/// Ctrl or Alt is held are kept: AltGr, which Windows reports as Ctrl and
+/// right Alt, is how many keyboard layouts type characters like `@` or `€`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate tcod;
+/// # extern crate tcod_window;
+/// #
+/// use tcod::input::{Key, KeyCode};
+/// use tcod_window::tcod_map_text;
+///
+/// # fn main() {
+/// let tcod_key = Key {
+///     code: KeyCode::Char,
+///     printable: 'A',
+///     shift: true,
+///     ..Key::default()
+/// };
+///
+/// assert_eq!(tcod_map_text(tcod_key), Some("A".to_owned()));
+/// # }
+/// ```
+pub fn tcod_map_text(key: TcodKey) -> Option<String> {
+    let chord = key.left_ctrl || key.right_ctrl || key.left_alt || key.right_alt;
+    match key.printable {
+        '\0' => None,
+        c if c.is_control() => None,
+        'a'...'z' | 'A'...'Z' | '0'...'9' if chord => None,
+        c => Some(c.to_string()),
     }
 }
 
 /// Maps a TCOD mouse state change to a piston-input button.
 ///
+/// Only the first button found to have changed is reported; use
+/// `tcod_map_mouse_buttons` to get every change.
+///
 /// # Examples
 ///
 /// ```
@@ -380,31 +948,132 @@ pub fn tcod_map_mouse(prev_state: Mouse, state: &Mouse) -> MouseButton {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    extern crate piston;
+/// Maps a TCOD mouse state change to piston-input button presses and releases.
+///
+/// Unlike `tcod_map_mouse`, every button whose state differs between
+/// `prev_state` and `state` is reported, in left, right, middle order.
+///
+/// TCOD sets the `*_pressed` flags of a mouse event when a button has been
+/// released since the last event. If that happened without the held state
+/// changing (i.e. a whole click fitted between two events), the missing press
+/// and release are synthesized.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate piston;
+/// # extern crate tcod;
+/// # extern crate tcod_window;
+/// #
+/// use piston::input::{Button, Input, MouseButton};
+/// use tcod::input::Mouse;
+/// use tcod_window::tcod_map_mouse_buttons;
+///
+/// # fn main() {
+/// let prev_state = Mouse {
+///     lbutton: true,
+///     ..Mouse::default()
+/// };
+/// let state = Mouse {
+///     rbutton: true,
+///     ..Mouse::default()
+/// };
+///
+/// assert_eq!(tcod_map_mouse_buttons(prev_state, &state),
+///            vec![Input::Release(Button::Mouse(MouseButton::Left)),
+///                 Input::Press(Button::Mouse(MouseButton::Right))]);
+/// # }
+/// ```
+pub fn tcod_map_mouse_buttons(prev_state: Mouse, state: &Mouse) -> Vec<Input> {
+    use input::Button;
+    use input::Input::{Press, Release};
 
-    use self::piston::window::{Size, WindowSettings};
+    let buttons = [(MouseButton::Left, prev_state.lbutton, state.lbutton, state.lbutton_pressed),
+                   (MouseButton::Right, prev_state.rbutton, state.rbutton, state.rbutton_pressed),
+                   (MouseButton::Middle,
+                    prev_state.mbutton,
+                    state.mbutton,
+                    state.mbutton_pressed)];
 
-    use super::TcodWindow;
-    use super::tcod::input::{Key, KeyCode};
+    let mut events = Vec::new();
+    for &(button, was_down, is_down, clicked) in &buttons {
+        let press = Press(Button::Mouse(button));
+        let release = Release(Button::Mouse(button));
 
-    fn tcod_key_from_keycode(key_code: KeyCode) -> Key {
-        Key {
-            code: key_code,
-            ..Key::default()
+        match (was_down, is_down, clicked) {
+            (false, true, _) => events.push(press),
+            (true, false, _) => events.push(release),
+            // The button was pressed and released between the two events.
+            (false, false, true) => events.extend_from_slice(&[press, release]),
+            // The button was released and pressed again between the two events.
+            (true, true, true) => events.extend_from_slice(&[release, press]),
+            _ => {},
         }
     }
 
-    fn tcod_key_from_char(c: char) -> Key {
-        Key {
-            code: KeyCode::Char,
-            printable: c,
+    events
+}
+
+/// Maps the mouse wheel state of a TCOD mouse event to a piston-input scroll
+/// motion.
+///
+/// Scrolling up yields a positive vertical scroll and scrolling down a negative
+/// one. Returns `None` if the wheel was not moved.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate piston;
+/// # extern crate tcod;
+/// # extern crate tcod_window;
+/// #
+/// use piston::input::Motion;
+/// use tcod::input::Mouse;
+/// use tcod_window::tcod_map_scroll;
+///
+/// # fn main() {
+/// let state = Mouse {
+///     wheel_up: true,
+///     ..Mouse::default()
+/// };
+///
+/// assert_eq!(tcod_map_scroll(&state), Some(Motion::MouseScroll(0.0, 1.0)));
+/// # }
+/// ```
+pub fn tcod_map_scroll(state: &Mouse) -> Option<Motion> {
+    match (state.wheel_up, state.wheel_down) {
+        (true, false) => Some(Motion::MouseScroll(0.0, 1.0)),
+        (false, true) => Some(Motion::MouseScroll(0.0, -1.0)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate piston;
+
+    use self::piston::window::{Size, WindowSettings};
+
+    use super::TcodWindow;
+    use super::tcod::input::{Key, KeyCode};
+
+    fn tcod_key_from_keycode(key_code: KeyCode) -> Key {
+        Key {
+            code: key_code,
+            ..Key::default()
+        }
+    }
+
+    fn tcod_key_from_char(c: char) -> Key {
+        Key {
+            code: KeyCode::Char,
+            printable: c,
             ..Key::default()
         }
     }
 
     #[test]
+    #[ignore] // Requires a display.
     fn test_new() {
         let _ = TcodWindow::new(
             WindowSettings::new(
@@ -415,52 +1084,622 @@ mod tests {
                 }
             )
         );
-    }
+    }
+
+    #[test]
+    #[ignore] // Requires a display.
+    fn test_from_console() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        use super::Error;
+        use super::tcod::console::Root;
+
+        let settings = WindowSettings::new(
+            "My Application".to_owned(),
+            Size {
+                width: 100,
+                height: 100,
+            }
+        );
+        let root = Root::initializer()
+                        .size(settings.get_size().width as i32,
+                              settings.get_size().height as i32)
+                        .title(settings.get_title())
+                        .init();
+        let console = Rc::new(RefCell::new(root));
+
+        let mut window = TcodWindow::with_console(console, settings);
+
+        // The font of an external console is unknown, so it cannot be rebuilt
+        match window.set_size(Size { width: 50, height: 50 }) {
+            Err(Error::ExternalConsole) => {},
+            _ => panic!("expected an external console error"),
+        }
+    }
+
+    #[test]
+    #[ignore] // Requires a display.
+    fn test_set_size_root() {
+        use self::piston::input::Input;
+        use self::piston::window::Window;
+
+        use super::TcodWindowBuilder;
+        use super::tcod::Console;
+        use super::tcod::console::FontLayout;
+
+        let mut window = TcodWindowBuilder::new(
+            WindowSettings::new(
+                "My Application".to_owned(),
+                Size {
+                    width: 80,
+                    height: 50,
+                }
+            )
+        )
+            .build()
+            .expect("Failed to build window.");
+        window.window.borrow_mut().set_char(0, 0, '@');
+
+        // The new root console is the one in use: the old `Root` did not
+        // tear it down
+        assert!(window.set_size(Size { width: 40, height: 25 }).is_ok());
+        assert_eq!(window.poll_event(), Some(Input::Resize(40, 25)));
+        assert_eq!(window.window.borrow().width(), 40);
+        assert_eq!(window.window.borrow().get_char(0, 0), '@');
+        window.swap_buffers();
+
+        // The same image read as a TCOD layout, 32 glyphs across and 8 down,
+        // has glyphs of a different size
+        let glyph_size = window.glyph_size();
+        assert!(window.set_font("terminal.png", FontLayout::Tcod).is_ok());
+        assert_eq!(window.poll_event(), Some(Input::Resize(40, 25)));
+        assert_eq!(window.glyph_size().width, glyph_size.width / 2);
+        assert_eq!(window.glyph_size().height, glyph_size.height * 2);
+        assert_eq!(window.window.borrow().get_char(0, 0), '@');
+        window.swap_buffers();
+
+        // Reloading the same font changes nothing
+        assert!(window.set_font("terminal.png", FontLayout::Tcod).is_ok());
+        assert_eq!(window.poll_event(), None);
+    }
+
+    #[test]
+    #[ignore] // Requires a display.
+    fn test_builder() {
+        use self::piston::window::Window;
+
+        use super::TcodWindowBuilder;
+        use super::tcod::console::{FontLayout, FontType, Renderer};
+
+        let settings = WindowSettings::new(
+            "My Application".to_owned(),
+            Size {
+                width: 80,
+                height: 50,
+            }
+        );
+
+        let window = TcodWindowBuilder::new(settings)
+            .font("terminal.png", FontLayout::AsciiInCol)
+            .font_type(FontType::Default)
+            .renderer(Renderer::SDL)
+            .fps(30)
+            .fullscreen(false)
+            .build()
+            .expect("Failed to build window.");
+
+        let size = window.size();
+        assert_eq!(size.width, 80);
+        assert_eq!(size.height, 50);
+        assert!(!window.is_fullscreen());
+        assert_eq!(window.renderer(), Renderer::SDL);
+    }
+
+    #[test]
+    fn test_builder_errors() {
+        use super::{Error, TcodWindowBuilder};
+        use super::tcod::console::FontLayout;
+
+        let settings = |width, height| {
+            WindowSettings::new(
+                "My Application".to_owned(),
+                Size {
+                    width: width,
+                    height: height,
+                }
+            )
+        };
+
+        match TcodWindowBuilder::new(settings(0, 100)).build() {
+            Err(Error::InvalidSize { width: 0, height: 100 }) => {},
+            _ => panic!("expected an invalid size error"),
+        }
+        match TcodWindowBuilder::new(settings(100, u32::max_value())).build() {
+            Err(Error::InvalidSize { .. }) => {},
+            _ => panic!("expected an invalid size error"),
+        }
+        match TcodWindowBuilder::new(settings(100, 100))
+                  .font("does_not_exist.png", FontLayout::AsciiInCol)
+                  .build() {
+            Err(Error::FontNotFound(ref path)) => {
+                assert_eq!(path.to_str(), Some("does_not_exist.png"))
+            },
+            _ => panic!("expected a font not found error"),
+        }
+    }
+
+    #[test]
+    fn test_has_display() {
+        use std::ffi::OsString;
+
+        use super::builder::has_display;
+
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|&&(var, _)| var == name)
+                    .map(|&(_, value)| OsString::from(value))
+            }
+        };
+
+        assert!(has_display(env(&[("DISPLAY", ":0")])));
+        assert!(has_display(env(&[("WAYLAND_DISPLAY", "wayland-0")])));
+        assert!(has_display(env(&[("SDL_VIDEODRIVER", "dummy")])));
+        assert!(has_display(env(&[("SDL_VIDEODRIVER", "kmsdrm")])));
+        assert!(!has_display(env(&[])));
+        assert!(!has_display(env(&[("DISPLAY", "")])));
+    }
+
+    #[test]
+    #[ignore] // Requires a display.
+    fn test_build_from_window_settings() {
+        let settings = WindowSettings::new(
+               "My Application".to_owned(),
+                Size {
+                    width: 100,
+                    height: 100,
+                }
+            )
+            .exit_on_esc(true);
+
+        let _: TcodWindow = settings.build()
+            .expect("Failed to build window.");
+    }
+
+    #[test]
+    fn test_window() {
+        use self::piston::window::Window;
+
+        let mut window = TcodWindow::headless(
+            WindowSettings::new(
+               "My Application".to_owned(),
+                Size {
+                    width: 100,
+                    height: 100,
+                }
+            )
+        );
+
+        assert!(!window.should_close());
+        window.set_should_close(true);
+        assert!(window.should_close());
+
+        assert!(!window.is_fullscreen());
+
+        window.swap_buffers();
+
+        let size = window.size();
+        assert_eq!(size.width, 100);
+        assert_eq!(size.height, 100);
+
+        assert_eq!(window.poll_event(), None);
+
+        let glyph_size = window.glyph_size();
+        assert_eq!(glyph_size.width, 8);
+        assert_eq!(glyph_size.height, 8);
+
+        let draw_size = window.draw_size();
+        assert_eq!(draw_size.width, 100 * glyph_size.width);
+        assert_eq!(draw_size.height, 100 * glyph_size.height);
+    }
+
+    #[test]
+    fn test_advanced_window() {
+        use self::piston::window::AdvancedWindow;
+
+        let mut window = TcodWindow::headless(
+            WindowSettings::new(
+               "My Application".to_owned(),
+                Size {
+                    width: 100,
+                    height: 100,
+                }
+            )
+        );
+
+        assert_eq!(window.get_title(), "My Application".to_owned());
+        window.set_title("some other name".to_owned());
+        assert_eq!(window.get_title(), "some other name".to_owned());
+
+        assert!(!window.get_exit_on_esc());
+        window.set_exit_on_esc(true);
+        assert!(window.get_exit_on_esc());
+
+        window.set_capture_cursor(true);
+    }
+
+    #[test]
+    fn test_capture_cursor() {
+        use std::collections::VecDeque;
+
+        use self::piston::input::{Input, Motion};
+        use self::piston::window::{AdvancedWindow, Window};
+
+        use super::CoordinateMode;
+        use super::tcod::input::{Event, MOUSE_MOVE, Mouse};
+
+        let mut window = TcodWindow::headless(
+            WindowSettings::new(
+               "My Application".to_owned(),
+                Size {
+                    width: 100,
+                    height: 100,
+                }
+            )
+        );
+        let mouse = |x, y| {
+            Mouse {
+                x: x,
+                y: y,
+                cx: x / 8,
+                cy: y / 8,
+                ..Mouse::default()
+            }
+        };
+
+        // The cursor is warped to the center of the 800x800 pixel window, and
+        // only motion relative to it is reported
+        window.set_capture_cursor(true);
+        let moves = || {
+            VecDeque::from(vec![(MOUSE_MOVE, Event::Mouse(mouse(416, 392))),
+                                (MOUSE_MOVE, Event::Mouse(mouse(400, 400))),
+                                (MOUSE_MOVE, Event::Mouse(mouse(404, 400)))])
+        };
+        for &(mode, first, last) in &[(CoordinateMode::Pixels, (16.0, -8.0), Some((4.0, 0.0))),
+                                      (CoordinateMode::Cells, (2.0, -1.0), None),
+                                      (CoordinateMode::FractionalCells,
+                                       (2.0, -1.0),
+                                       Some((0.5, 0.0)))] {
+            window.set_coordinate_mode(mode);
+            window.set_event_source(moves());
+            assert_eq!(window.poll_event(),
+                       Some(Input::Move(Motion::MouseRelative(first.0, first.1))));
+            // Landing on the center, or on its cell in `Cells` mode, reports
+            // nothing
+            assert_eq!(window.poll_event(),
+                       last.map(|(x, y)| Input::Move(Motion::MouseRelative(x, y))));
+            assert_eq!(window.poll_event(), None);
+        }
+
+        // Releasing the cursor reports its position again
+        window.set_capture_cursor(false);
+        window.set_coordinate_mode(CoordinateMode::Pixels);
+        window.set_event_source(VecDeque::from(vec![(MOUSE_MOVE, Event::Mouse(mouse(10, 20)))]));
+        assert_eq!(window.poll_event(), Some(Input::Move(Motion::MouseCursor(10.0, 20.0))));
+        assert_eq!(window.poll_event(), Some(Input::Move(Motion::MouseRelative(-394.0, -380.0))));
+        assert_eq!(window.poll_event(), None);
+    }
+
+    #[test]
+    fn test_headless_events() {
+        use std::collections::VecDeque;
+
+        use self::piston::input::{Button, Input, Key as PistonKey, Motion};
+        use self::piston::input::mouse::MouseButton;
+        use self::piston::window::Window;
+
+        use super::tcod::input::{Event, KEY_PRESS, KEY_RELEASE, MOUSE_MOVE, MOUSE_PRESS, Mouse};
+
+        let mut window = TcodWindow::headless(
+            WindowSettings::new(
+               "My Application".to_owned(),
+                Size {
+                    width: 100,
+                    height: 100,
+                }
+            )
+            .exit_on_esc(true)
+        );
+
+        // Key presses
+        window.set_event_source(VecDeque::from(vec![
+            (KEY_PRESS, Event::Key(Key { shift: true, ..tcod_key_from_char('A') })),
+            (KEY_RELEASE, Event::Key(tcod_key_from_char('a'))),
+        ]));
+        assert_eq!(window.poll_event(), Some(Input::Press(Button::Keyboard(PistonKey::A))));
+        assert_eq!(window.poll_event(), Some(Input::Text("A".to_owned())));
+        assert_eq!(window.poll_event(), Some(Input::Release(Button::Keyboard(PistonKey::A))));
+        assert_eq!(window.poll_event(), None);
+
+        // Right-hand modifiers
+        let right_ctrl = Key { right_ctrl: true, ..tcod_key_from_keycode(KeyCode::Control) };
+        window.set_event_source(VecDeque::from(vec![
+            (KEY_PRESS, Event::Key(right_ctrl)),
+            (KEY_RELEASE, Event::Key(tcod_key_from_keycode(KeyCode::Control))),
+        ]));
+        assert_eq!(window.poll_event(), Some(Input::Press(Button::Keyboard(PistonKey::RCtrl))));
+        assert_eq!(window.poll_event(),
+                   Some(Input::Release(Button::Keyboard(PistonKey::RCtrl))));
+
+        // Mouse motion, buttons and wheel
+        let mouse = Mouse {
+            x: 16,
+            y: 24,
+            cx: 2,
+            cy: 3,
+            ..Mouse::default()
+        };
+        window.set_event_source(VecDeque::from(vec![
+            (MOUSE_MOVE, Event::Mouse(mouse)),
+            (MOUSE_PRESS, Event::Mouse(Mouse { lbutton: true, ..mouse })),
+            (MOUSE_PRESS, Event::Mouse(Mouse { wheel_down: true, ..mouse })),
+        ]));
+        assert_eq!(window.poll_event(), Some(Input::Move(Motion::MouseCursor(16.0, 24.0))));
+        assert_eq!(window.poll_event(), Some(Input::Move(Motion::MouseRelative(16.0, 24.0))));
+        assert_eq!(window.poll_event(), Some(Input::Press(Button::Mouse(MouseButton::Left))));
+        assert_eq!(window.poll_event(), Some(Input::Release(Button::Mouse(MouseButton::Left))));
+        assert_eq!(window.poll_event(), Some(Input::Move(Motion::MouseScroll(0.0, -1.0))));
+        assert_eq!(window.poll_event(), None);
+
+        // Alt+Enter toggles fullscreen
+        window.set_fullscreen_toggle(true);
+        let alt_enter = Key { left_alt: true, ..tcod_key_from_keycode(KeyCode::Enter) };
+        window.set_event_source(VecDeque::from(vec![(KEY_PRESS, Event::Key(alt_enter))]));
+        // The console keeps its size in cells, but the window grows to the
+        // size of the screen
+        assert_eq!(window.poll_event(), Some(Input::Resize(100, 100)));
+        assert_eq!(window.poll_event(), None);
+        assert!(window.is_fullscreen());
+        let draw_size = window.draw_size();
+        assert_eq!(draw_size.width, 1280);
+        assert_eq!(draw_size.height, 800);
+        window.set_fullscreen(false);
+        assert_eq!(window.poll_event(), Some(Input::Resize(100, 100)));
+        assert_eq!(window.draw_size().width, 800);
+
+        // Escape closes the window
+        window.set_event_source(VecDeque::from(vec![
+            (KEY_PRESS, Event::Key(tcod_key_from_keycode(KeyCode::Escape))),
+        ]));
+        assert_eq!(window.poll_event(), None);
+        assert!(window.should_close());
+
+        // So does closing the window
+        window.set_should_close(false);
+        window.window.borrow_mut().close();
+        assert_eq!(window.poll_event(), None);
+        assert!(window.should_close());
+    }
+
+    #[test]
+    fn test_event_source() {
+        use std::collections::VecDeque;
+
+        use self::piston::input::{Button, Input, Key as PistonKey};
+        use self::piston::window::Window;
+
+        use super::EventSource;
+        use super::tcod::input::{Event, EventFlags, KEY_PRESS, KEY_RELEASE};
+
+        struct Repeat(usize);
+
+        impl EventSource for Repeat {
+            fn next_event(&mut self) -> Option<(EventFlags, Event)> {
+                if self.0 == 0 {
+                    return None;
+                }
+
+                self.0 -= 1;
+                Some((KEY_PRESS, Event::Key(tcod_key_from_keycode(KeyCode::Tab))))
+            }
+        }
+
+        let mut window = TcodWindow::headless(
+            WindowSettings::new(
+               "My Application".to_owned(),
+                Size {
+                    width: 100,
+                    height: 100,
+                }
+            )
+        );
+
+        window.set_event_source(Repeat(2));
+        assert_eq!(window.poll_event(), Some(Input::Press(Button::Keyboard(PistonKey::Tab))));
+        assert_eq!(window.poll_event(), Some(Input::Press(Button::Keyboard(PistonKey::Tab))));
+        assert_eq!(window.poll_event(), None);
+
+        // A queue of events is itself an event source.
+        let mut events = VecDeque::new();
+        events.push_back((KEY_PRESS, Event::Key(tcod_key_from_keycode(KeyCode::Up))));
+        events.push_back((KEY_RELEASE, Event::Key(tcod_key_from_keycode(KeyCode::Tab))));
+        window.set_event_source(events);
+        assert_eq!(window.poll_event(), Some(Input::Press(Button::Keyboard(PistonKey::Up))));
+        assert_eq!(window.poll_event(), Some(Input::Release(Button::Keyboard(PistonKey::Tab))));
+        assert_eq!(window.poll_event(), None);
+    }
+
+    #[test]
+    fn test_input_state() {
+        use std::collections::VecDeque;
+
+        use self::piston::input::{Input, Key as PistonKey, keyboard};
+        use self::piston::input::mouse::MouseButton;
+        use self::piston::window::Window;
+
+        use super::tcod::input::{Event, KEY_PRESS, KEY_RELEASE, MOUSE_PRESS, Mouse};
+
+        let mut window = TcodWindow::headless(
+            WindowSettings::new(
+               "My Application".to_owned(),
+                Size {
+                    width: 100,
+                    height: 100,
+                }
+            )
+        );
+
+        let right_alt = Key { right_alt: true, ..tcod_key_from_keycode(KeyCode::Alt) };
+        let mouse = Mouse {
+            x: 20,
+            y: 36,
+            cx: 2,
+            cy: 4,
+            rbutton: true,
+            ..Mouse::default()
+        };
+        window.set_event_source(VecDeque::from(vec![
+            (KEY_PRESS, Event::Key(right_alt)),
+            (KEY_PRESS, Event::Key(tcod_key_from_char('w'))),
+            (KEY_RELEASE, Event::Key(tcod_key_from_char('w'))),
+            (MOUSE_PRESS, Event::Mouse(mouse)),
+        ]));
+
+        // The state follows the events as they are returned.
+        window.poll_event();
+        window.poll_event();
+        assert!(window.input_state().is_key_down(PistonKey::RAlt));
+        assert!(window.input_state().is_key_down(PistonKey::W));
+        assert_eq!(window.input_state().modifiers(), keyboard::ALT);
+
+        while let Some(_) = window.poll_event() {}
+        assert!(!window.input_state().is_key_down(PistonKey::W));
+        assert!(window.input_state().is_mouse_down(MouseButton::Right));
+        assert!(!window.input_state().is_mouse_down(MouseButton::Left));
+        assert_eq!(window.input_state().mouse_position(), (20.0, 36.0));
+        assert_eq!(window.input_state().mouse_cell(), (2, 4));
+
+        // Losing focus releases everything.
+        window.window.borrow_mut().set_active(false);
+        assert_eq!(window.poll_event(), Some(Input::Focus(false)));
+        assert!(!window.input_state().is_key_down(PistonKey::RAlt));
+        assert!(!window.input_state().is_mouse_down(MouseButton::Right));
+        assert_eq!(window.input_state().modifiers(), keyboard::NO_MODIFIER);
+
+        // Presses still queued when focus is lost are released along with the
+        // rest once the focus event is returned.
+        window.window.borrow_mut().set_active(true);
+        assert_eq!(window.poll_event(), Some(Input::Focus(true)));
+        window.set_event_source(VecDeque::from(vec![
+            (MOUSE_PRESS, Event::Mouse(Mouse { lbutton: true, mbutton: true, ..mouse })),
+        ]));
+        window.poll_event();
+        window.window.borrow_mut().set_active(false);
+        window.poll_event();
+        assert!(window.input_state().is_mouse_down(MouseButton::Left));
+        assert!(window.input_state().is_mouse_down(MouseButton::Middle));
+        assert_eq!(window.poll_event(), Some(Input::Focus(false)));
+        assert!(!window.input_state().is_mouse_down(MouseButton::Left));
+        assert!(!window.input_state().is_mouse_down(MouseButton::Middle));
+    }
+
+    #[test]
+    fn test_focus_events() {
+        use self::piston::input::Input;
+        use self::piston::window::Window;
+
+        let mut window = TcodWindow::headless(
+            WindowSettings::new(
+               "My Application".to_owned(),
+                Size {
+                    width: 100,
+                    height: 100,
+                }
+            )
+        );
 
-    #[test]
-    fn test_from_console() {
-        use std::cell::RefCell;
-        use std::rc::Rc;
+        assert_eq!(window.poll_event(), None);
 
-        use super::tcod::console::Root;
+        window.window.borrow_mut().set_active(false);
+        assert_eq!(window.poll_event(), Some(Input::Focus(false)));
+        assert_eq!(window.poll_event(), None);
 
-        let settings = WindowSettings::new(
-            "My Application".to_owned(),
-            Size {
-                width: 100,
-                height: 100,
-            }
-        );
-        let root = Root::initializer()
-                        .size(settings.get_size().width as i32,
-                              settings.get_size().height as i32)
-                        .title(settings.get_title())
-                        .init();
-        let console = Rc::new(RefCell::new(root));
+        window.window.borrow_mut().set_active(true);
+        assert_eq!(window.poll_event(), Some(Input::Focus(true)));
+        assert_eq!(window.poll_event(), None);
 
-        let _ = TcodWindow::with_console(console, settings);
+        // The mouse pointer leaving the window does not take the keyboard focus.
+        window.window.borrow_mut().set_mouse_focus(false);
+        assert_eq!(window.poll_event(), None);
+        window.window.borrow_mut().set_mouse_focus(true);
+        assert_eq!(window.poll_event(), None);
     }
 
     #[test]
-    fn test_build_from_window_settings() {
-        let settings = WindowSettings::new(
+    fn test_set_size() {
+        use self::piston::input::Input;
+        use self::piston::window::Window;
+
+        use super::tcod::Console;
+
+        let mut window = TcodWindow::headless(
+            WindowSettings::new(
                "My Application".to_owned(),
                 Size {
-                    width: 100,
-                    height: 100,
+                    width: 10,
+                    height: 10,
                 }
             )
-            .exit_on_esc(true);
+        );
+        window.window.borrow_mut().set_char(0, 0, '@');
+        window.window.borrow_mut().set_char(9, 9, '#');
 
-        let _: TcodWindow = settings.build()
-            .expect("Failed to build window.");
+        // Shrinking keeps the cells which still fit
+        assert!(window.set_size(Size { width: 5, height: 4 }).is_ok());
+        let size = window.size();
+        assert_eq!(size.width, 5);
+        assert_eq!(size.height, 4);
+        assert_eq!(window.window.borrow().get_char(0, 0), '@');
+        assert_eq!(window.poll_event(), Some(Input::Resize(5, 4)));
+
+        // Growing again leaves the new cells blank
+        assert!(window.set_size(Size { width: 10, height: 10 }).is_ok());
+        assert_eq!(window.window.borrow().get_char(0, 0), '@');
+        assert_eq!(window.window.borrow().get_char(9, 9), ' ');
+        assert_eq!(window.poll_event(), Some(Input::Resize(10, 10)));
+
+        // Keeping the same size does not emit a resize
+        assert!(window.set_size(Size { width: 10, height: 10 }).is_ok());
+        assert_eq!(window.poll_event(), None);
+
+        // Invalid sizes are rejected without a resize
+        assert!(window.set_size(Size { width: 0, height: 10 }).is_err());
+        let size = window.size();
+        assert_eq!(size.width, 10);
+        assert_eq!(size.height, 10);
+        assert_eq!(window.poll_event(), None);
     }
 
     #[test]
-    fn test_window() {
-        use self::piston::window::Window;
+    fn test_wait_event() {
+        use std::collections::VecDeque;
+        use std::time::{Duration, Instant};
+
+        use self::piston::input::{Button, Input, Key as PistonKey};
 
-        let mut window = TcodWindow::new(
+        use super::EventSource;
+        use super::tcod::input::{Event, EventFlags, KEY_PRESS};
+
+        struct Never;
+
+        impl EventSource for Never {
+            fn next_event(&mut self) -> Option<(EventFlags, Event)> {
+                None
+            }
+        }
+
+        let mut window = TcodWindow::headless(
             WindowSettings::new(
                "My Application".to_owned(),
                 Size {
@@ -470,28 +1709,81 @@ mod tests {
             )
         );
 
-        assert!(!window.should_close());
-        window.set_should_close(true);
-        assert!(window.should_close());
-
-        window.swap_buffers();
+        let mut events = VecDeque::new();
+        events.push_back((KEY_PRESS, Event::Key(tcod_key_from_keycode(KeyCode::Home))));
+        window.set_event_source(events);
+        assert_eq!(window.wait_event(), Some(Input::Press(Button::Keyboard(PistonKey::Home))));
+        assert_eq!(window.wait_event(), None);
 
-        let size = window.size();
-        assert_eq!(size.width, 100);
-        assert_eq!(size.height, 100);
+        window.set_event_source(Never);
+        let start = Instant::now();
+        assert_eq!(window.wait_event_timeout(Duration::from_millis(20)), None);
+        assert!(start.elapsed() >= Duration::from_millis(20));
 
-        assert_eq!(window.poll_event(), None);
+        window.set_event_source(VecDeque::from(vec![
+            (KEY_PRESS, Event::Key(tcod_key_from_keycode(KeyCode::End))),
+        ]));
+        assert_eq!(window.wait_event_timeout(Duration::from_millis(20)),
+                   Some(Input::Press(Button::Keyboard(PistonKey::End))));
 
-        let draw_size = window.draw_size();
-        assert_eq!(draw_size.width, 100);
-        assert_eq!(draw_size.height, 100);
+        window.window.borrow_mut().close();
+        assert_eq!(window.wait_event_timeout(Duration::from_secs(60)), None);
+        assert!(window.should_close());
     }
 
     #[test]
-    fn test_advanced_window() {
-        use self::piston::window::AdvancedWindow;
+    fn test_record_replay() {
+        use std::collections::VecDeque;
+        use std::io::{self, Write};
+
+        use self::piston::input::{Button, Input, Key as PistonKey, Motion};
+        use self::piston::window::Window;
+
+        use super::EventSource;
+        use super::record::{Recorder, Replay};
+        use super::tcod::input::{Event, KEY_PRESS, MOUSE_MOVE, Mouse};
+
+        let key = Key { shift: true, right_ctrl: true, ..tcod_key_from_char('é') };
+        let mouse = Mouse {
+            x: -3,
+            y: 40,
+            cx: 1,
+            cy: 5,
+            lbutton: true,
+            wheel_up: true,
+            ..Mouse::default()
+        };
+
+        let mut events = VecDeque::new();
+        events.push_back((KEY_PRESS, Event::Key(key)));
+        events.push_back((MOUSE_MOVE, Event::Mouse(mouse)));
+
+        let mut recording = Vec::new();
+        {
+            let mut recorder = Recorder::new(events, &mut recording).unwrap();
+            assert!(recorder.next_event().is_some());
+            assert!(recorder.next_event().is_none());
+            assert!(recorder.next_event().is_some());
+            assert!(recorder.next_event().is_none());
+            assert!(recorder.error().is_none());
+        }
 
-        let mut window = TcodWindow::new(
+        let mut replay = Replay::from_reader(&recording[..]).unwrap();
+        match replay.next_event() {
+            Some((KEY_PRESS, Event::Key(replayed))) => {
+                assert_eq!(replayed.code, KeyCode::Char);
+                assert_eq!(replayed.printable, 'é');
+                assert!(replayed.shift);
+                assert!(replayed.right_ctrl);
+                assert!(!replayed.left_ctrl);
+            },
+            _ => panic!("expected a key press"),
+        }
+        // The mouse event was recorded in the next frame.
+        assert!(replay.next_event().is_none());
+        assert!(!replay.is_finished());
+
+        let mut window = TcodWindow::headless(
             WindowSettings::new(
                "My Application".to_owned(),
                 Size {
@@ -500,16 +1792,49 @@ mod tests {
                 }
             )
         );
+        window.replay(&recording[..]).unwrap();
+        assert_eq!(window.poll_event(), Some(Input::Press(Button::Keyboard(PistonKey::Unknown))));
+        assert_eq!(window.poll_event(), Some(Input::Text("é".to_owned())));
+        assert_eq!(window.poll_event(), None);
+        assert_eq!(window.poll_event(), Some(Input::Move(Motion::MouseCursor(-3.0, 40.0))));
 
-        assert_eq!(window.get_title(), "My Application".to_owned());
-        window.set_title("some other name".to_owned());
-        assert_eq!(window.get_title(), "some other name".to_owned());
+        assert!(Replay::from_reader(&b"some other file"[..]).is_err());
+        assert!(Replay::from_reader(&b"tcod_window-recording 2"[..]).is_err());
+        assert!(Replay::from_reader(&b"tcod_window-recording 1\n0 0 key_press"[..]).is_err());
 
-        assert!(!window.get_exit_on_esc());
-        window.set_exit_on_esc(true);
-        assert!(window.get_exit_on_esc());
+        // A writer which fails to write more than a given number of bytes
+        struct Limited(usize);
 
-        window.set_capture_cursor(true);
+        impl Write for Limited {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if buf.len() > self.0 {
+                    return Err(io::Error::new(io::ErrorKind::Other, "disk full"));
+                }
+                self.0 -= buf.len();
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        // Recording from a window, whose events keep flowing once a write fails
+        let header_len = "tcod_window-recording 1\n".len();
+        let up = tcod_key_from_keycode(KeyCode::Up);
+        window.set_event_source(VecDeque::from(vec![(KEY_PRESS, Event::Key(up))]));
+        assert!(window.stop_recording().is_none());
+        assert!(window.record(Limited(header_len)).is_ok());
+        assert_eq!(window.poll_event(), Some(Input::Press(Button::Keyboard(PistonKey::Up))));
+        assert!(window.stop_recording().is_some());
+        assert!(window.stop_recording().is_none());
+        assert!(window.record(Limited(0)).is_err());
+
+        // Stopping a recording leaves the recorded source in place
+        assert!(window.record(Limited(usize::max_value())).is_ok());
+        window.set_event_source(VecDeque::from(vec![(MOUSE_MOVE, Event::Mouse(mouse))]));
+        assert!(window.stop_recording().is_none());
+        assert_eq!(window.poll_event(), Some(Input::Move(Motion::MouseCursor(-3.0, 40.0))));
     }
 
     #[test]
@@ -527,6 +1852,23 @@ mod tests {
         assert_eq!(tcod_map_key(tcod_key_from_keycode(KeyCode::Shift)), PistonKey::LShift);
         assert_eq!(tcod_map_key(tcod_key_from_keycode(KeyCode::Control)), PistonKey::LCtrl);
         assert_eq!(tcod_map_key(tcod_key_from_keycode(KeyCode::Alt)), PistonKey::LAlt);
+        // Left and right modifiers
+        let key = |code, left, right| Key {
+            code: code,
+            left_ctrl: left && code == KeyCode::Control,
+            right_ctrl: right && code == KeyCode::Control,
+            left_alt: left && code == KeyCode::Alt,
+            right_alt: right && code == KeyCode::Alt,
+            ..Key::default()
+        };
+        assert_eq!(tcod_map_key(key(KeyCode::Control, true, false)), PistonKey::LCtrl);
+        assert_eq!(tcod_map_key(key(KeyCode::Control, false, true)), PistonKey::RCtrl);
+        assert_eq!(tcod_map_key(key(KeyCode::Control, true, true)), PistonKey::LCtrl);
+        assert_eq!(tcod_map_key(key(KeyCode::Alt, true, false)), PistonKey::LAlt);
+        assert_eq!(tcod_map_key(key(KeyCode::Alt, false, true)), PistonKey::RAlt);
+        assert_eq!(tcod_map_key(key(KeyCode::Alt, true, true)), PistonKey::LAlt);
+        assert_eq!(tcod_map_key(Key { shift: true, ..tcod_key_from_keycode(KeyCode::Shift) }),
+                   PistonKey::LShift);
         assert_eq!(tcod_map_key(tcod_key_from_keycode(KeyCode::Pause)), PistonKey::Pause);
         assert_eq!(tcod_map_key(tcod_key_from_keycode(KeyCode::CapsLock)), PistonKey::CapsLock);
         assert_eq!(tcod_map_key(tcod_key_from_keycode(KeyCode::PageUp)), PistonKey::PageUp);
@@ -653,6 +1995,346 @@ mod tests {
         assert_eq!(tcod_map_key(tcod_key_from_char(')')), PistonKey::RightParen);
     }
 
+    #[test]
+    fn test_tcod_map_key_chars() {
+        use std::collections::VecDeque;
+
+        use std::char;
+
+        use self::piston::input::{Button, Input, Key as PistonKey};
+
+        use super::tcod::input::{Event, KEY_PRESS};
+        use super::tcod_map_key;
+
+        let expected = [
+            (' ', PistonKey::Space),
+            ('!', PistonKey::Exclaim),
+            ('"', PistonKey::Quotedbl),
+            ('#', PistonKey::Hash),
+            ('$', PistonKey::Dollar),
+            ('%', PistonKey::Percent),
+            ('&', PistonKey::Ampersand),
+            ('\'', PistonKey::Quote),
+            ('(', PistonKey::LeftParen),
+            (')', PistonKey::RightParen),
+            ('*', PistonKey::Asterisk),
+            ('+', PistonKey::Plus),
+            (',', PistonKey::Comma),
+            ('-', PistonKey::Minus),
+            ('.', PistonKey::Period),
+            ('/', PistonKey::Slash),
+            ('0', PistonKey::D0),
+            ('1', PistonKey::D1),
+            ('2', PistonKey::D2),
+            ('3', PistonKey::D3),
+            ('4', PistonKey::D4),
+            ('5', PistonKey::D5),
+            ('6', PistonKey::D6),
+            ('7', PistonKey::D7),
+            ('8', PistonKey::D8),
+            ('9', PistonKey::D9),
+            (':', PistonKey::Colon),
+            (';', PistonKey::Semicolon),
+            ('<', PistonKey::Less),
+            ('=', PistonKey::Equals),
+            ('>', PistonKey::Greater),
+            ('?', PistonKey::Question),
+            ('@', PistonKey::At),
+            ('[', PistonKey::LeftBracket),
+            ('\\', PistonKey::Backslash),
+            (']', PistonKey::RightBracket),
+            ('^', PistonKey::Caret),
+            ('_', PistonKey::Underscore),
+            ('`', PistonKey::Backquote),
+            ('a', PistonKey::A),
+            ('b', PistonKey::B),
+            ('c', PistonKey::C),
+            ('d', PistonKey::D),
+            ('e', PistonKey::E),
+            ('f', PistonKey::F),
+            ('g', PistonKey::G),
+            ('h', PistonKey::H),
+            ('i', PistonKey::I),
+            ('j', PistonKey::J),
+            ('k', PistonKey::K),
+            ('l', PistonKey::L),
+            ('m', PistonKey::M),
+            ('n', PistonKey::N),
+            ('o', PistonKey::O),
+            ('p', PistonKey::P),
+            ('q', PistonKey::Q),
+            ('r', PistonKey::R),
+            ('s', PistonKey::S),
+            ('t', PistonKey::T),
+            ('u', PistonKey::U),
+            ('v', PistonKey::V),
+            ('w', PistonKey::W),
+            ('x', PistonKey::X),
+            ('y', PistonKey::Y),
+            ('z', PistonKey::Z),
+        ];
+
+        // Every ASCII character Piston has a key for maps to it, regardless of
+        // case, and every other character maps to `Unknown`
+        for code in 0..0x3000 {
+            let c = match char::from_u32(code) {
+                Some(c) => c,
+                None => continue,
+            };
+            let key = expected.iter()
+                              .find(|&&(expected, _)| {
+                                  expected == c || expected.to_uppercase().next() == Some(c)
+                              })
+                              .map_or(PistonKey::Unknown, |&(_, key)| key);
+            assert_eq!(tcod_map_key(tcod_key_from_char(c)), key, "{:?}", c);
+        }
+
+        // The character itself is kept in the text of the key press
+        let mut window = TcodWindow::headless(
+            WindowSettings::new(
+               "My Application".to_owned(),
+                Size {
+                    width: 100,
+                    height: 100,
+                }
+            )
+        );
+        for &(c, key) in &[('<', PistonKey::Less), ('~', PistonKey::Unknown),
+                           ('é', PistonKey::Unknown), ('ß', PistonKey::Unknown)] {
+            window.set_event_source(VecDeque::from(vec![
+                (KEY_PRESS, Event::Key(tcod_key_from_char(c))),
+            ]));
+            assert_eq!(window.poll_event(), Some(Input::Press(Button::Keyboard(key))));
+            assert_eq!(window.poll_event(), Some(Input::Text(c.to_string())));
+        }
+    }
+
+    #[test]
+    fn test_tcod_map_key_chords() {
+        use std::collections::VecDeque;
+
+        use self::piston::input::{Button, Input, Key as PistonKey};
+
+        use super::tcod::input::{Event, KEY_PRESS, KEY_RELEASE};
+        use super::tcod_map_key;
+
+        let letters = [PistonKey::A, PistonKey::B, PistonKey::C, PistonKey::D, PistonKey::E,
+                       PistonKey::F, PistonKey::G, PistonKey::H, PistonKey::I, PistonKey::J,
+                       PistonKey::K, PistonKey::L, PistonKey::M, PistonKey::N, PistonKey::O,
+                       PistonKey::P, PistonKey::Q, PistonKey::R, PistonKey::S, PistonKey::T,
+                       PistonKey::U, PistonKey::V, PistonKey::W, PistonKey::X, PistonKey::Y,
+                       PistonKey::Z];
+
+        for (i, &letter) in letters.iter().enumerate() {
+            let c = (b'a' + i as u8) as char;
+            let control = (i as u8 + 1) as char;
+
+            // Ctrl turns letters into control characters
+            let ctrl = Key { left_ctrl: true, ..tcod_key_from_char(control) };
+            let right_ctrl = Key { right_ctrl: true, ..tcod_key_from_char(control) };
+            let ctrl_alt = Key { left_alt: true, ..ctrl };
+            assert_eq!(tcod_map_key(ctrl), letter);
+            assert_eq!(tcod_map_key(right_ctrl), letter);
+            assert_eq!(tcod_map_key(ctrl_alt), letter);
+
+            // ...unless the letter itself is reported
+            let ctrl_shift = Key { left_ctrl: true, shift: true, ..tcod_key_from_char(c) };
+            assert_eq!(tcod_map_key(ctrl_shift), letter);
+
+            let alt = Key { left_alt: true, ..tcod_key_from_char(c) };
+            let right_alt = Key { right_alt: true, ..tcod_key_from_char(c) };
+            assert_eq!(tcod_map_key(alt), letter);
+            assert_eq!(tcod_map_key(right_alt), letter);
+
+            // Control characters only stand for letters while Ctrl is held
+            assert_eq!(tcod_map_key(tcod_key_from_char(control)), PistonKey::Unknown);
+        }
+
+        // Digits reported without a printable character
+        let ctrl_digit = Key { left_ctrl: true, ..tcod_key_from_keycode(KeyCode::Number5) };
+        assert_eq!(tcod_map_key(ctrl_digit), PistonKey::D5);
+
+        // Chords reach the application as the letter key, without any text
+        let mut window = TcodWindow::headless(
+            WindowSettings::new(
+               "My Application".to_owned(),
+                Size {
+                    width: 100,
+                    height: 100,
+                }
+            )
+        );
+        let ctrl_s = Key { left_ctrl: true, ..tcod_key_from_char('\x13') };
+        window.set_event_source(VecDeque::from(vec![
+            (KEY_PRESS, Event::Key(ctrl_s)),
+            (KEY_RELEASE, Event::Key(ctrl_s)),
+        ]));
+        assert_eq!(window.poll_event(), Some(Input::Press(Button::Keyboard(PistonKey::S))));
+        assert_eq!(window.poll_event(), Some(Input::Release(Button::Keyboard(PistonKey::S))));
+        assert_eq!(window.poll_event(), None);
+    }
+
+    #[test]
+    fn test_piston_map_key() {
+        use self::piston::input::Key as PistonKey;
+
+        use super::{piston_map_key, tcod_map_key};
+        use super::keymap::KEY_CODES;
+
+        let right_ctrl = Key { right_ctrl: true, ..tcod_key_from_keycode(KeyCode::Control) };
+        let right_alt = Key { right_alt: true, ..tcod_key_from_keycode(KeyCode::Alt) };
+        let tcod_keys = KEY_CODES.iter()
+                                 .cloned()
+                                 .map(tcod_key_from_keycode)
+                                 .chain((0x20u8..0x7f).map(|c| tcod_key_from_char(c as char)))
+                                 .chain(vec![right_ctrl, right_alt]);
+
+        // Every key TCOD can report maps back to itself
+        for tcod_key in tcod_keys {
+            let piston_key = tcod_map_key(tcod_key);
+            if piston_key == PistonKey::Unknown {
+                continue;
+            }
+
+            let mapped = piston_map_key(piston_key);
+            assert!(mapped.is_some(), "{:?} has no TCOD key", piston_key);
+            assert_eq!(tcod_map_key(mapped.unwrap()), piston_key);
+        }
+
+        assert!(piston_map_key(PistonKey::Unknown).is_none());
+        assert!(piston_map_key(PistonKey::F24).is_none());
+
+        let digit = piston_map_key(PistonKey::D7).unwrap();
+        assert_eq!(digit.code, KeyCode::Number7);
+        assert_eq!(digit.printable, '7');
+
+        let letter = piston_map_key(PistonKey::Q).unwrap();
+        assert_eq!(letter.code, KeyCode::Char);
+        assert_eq!(letter.printable, 'q');
+
+        let rctrl = piston_map_key(PistonKey::RCtrl).unwrap();
+        assert_eq!(rctrl.code, KeyCode::Control);
+        assert!(rctrl.right_ctrl && !rctrl.left_ctrl);
+
+        assert_eq!(piston_map_key(PistonKey::RShift).unwrap().code, KeyCode::Shift);
+    }
+
+    #[test]
+    fn test_key_map() {
+        use std::collections::VecDeque;
+
+        use self::piston::input::{Button, Input, Key as PistonKey};
+
+        use super::{Error, KeyMap};
+        use super::tcod::input::{Event, KEY_PRESS};
+
+        let mut key_map = KeyMap::new();
+        key_map.set_code(KeyCode::Escape, PistonKey::Backspace);
+        key_map.set_code(KeyCode::Control, PistonKey::CapsLock);
+        key_map.set_char('Q', PistonKey::A);
+
+        assert_eq!(key_map.map(tcod_key_from_keycode(KeyCode::Escape)), PistonKey::Backspace);
+        assert_eq!(key_map.map(tcod_key_from_char('q')), PistonKey::A);
+        assert_eq!(key_map.map(tcod_key_from_char('Q')), PistonKey::A);
+        // Keys without a binding keep their default translation
+        assert_eq!(key_map.map(tcod_key_from_char('w')), PistonKey::W);
+        assert_eq!(key_map.map(tcod_key_from_keycode(KeyCode::Up)), PistonKey::Up);
+
+        // The side of a modifier is only told apart if it is still bound to one
+        let right_ctrl = Key { right_ctrl: true, ..tcod_key_from_keycode(KeyCode::Control) };
+        let right_alt = Key { right_alt: true, ..tcod_key_from_keycode(KeyCode::Alt) };
+        assert_eq!(key_map.map(right_ctrl), PistonKey::CapsLock);
+        assert_eq!(key_map.map(right_alt), PistonKey::RAlt);
+
+        // Loading from a file
+        let file = "# A comment\n\
+                    Escape = Backspace\n\
+                    \n\
+                    'q' = A\n\
+                    '=' = Minus\n\
+                    '#' = Hash\n";
+        let key_map = KeyMap::from_reader(file.as_bytes()).unwrap();
+        assert_eq!(key_map.map(tcod_key_from_keycode(KeyCode::Escape)), PistonKey::Backspace);
+        assert_eq!(key_map.map(tcod_key_from_char('q')), PistonKey::A);
+        assert_eq!(key_map.map(tcod_key_from_char('=')), PistonKey::Minus);
+        assert_eq!(key_map.map(tcod_key_from_char('#')), PistonKey::Hash);
+
+        for &(file, line) in &[("Escape Backspace", 1),
+                               ("Escape = Backspace\nEscape = Nothing", 2),
+                               ("Nothing = Backspace", 1),
+                               ("'ab' = A", 1)] {
+            match KeyMap::from_reader(file.as_bytes()) {
+                Err(Error::InvalidKeyMap { line: l, .. }) => assert_eq!(l, line),
+                result => panic!("expected an invalid key map, got {:?}", result),
+            }
+        }
+
+        // Windows translate keys through their key map
+        let mut window = TcodWindow::headless(
+            WindowSettings::new(
+               "My Application".to_owned(),
+                Size {
+                    width: 100,
+                    height: 100,
+                }
+            )
+        );
+        window.set_key_map(key_map);
+        window.set_event_source(VecDeque::from(vec![
+            (KEY_PRESS, Event::Key(tcod_key_from_keycode(KeyCode::Escape))),
+        ]));
+        assert_eq!(window.poll_event(),
+                   Some(Input::Press(Button::Keyboard(PistonKey::Backspace))));
+
+        // Bound modifiers are reported as bound, whichever side is pressed
+        let mut key_map = KeyMap::new();
+        key_map.set_code(KeyCode::Control, PistonKey::RCtrl);
+        key_map.set_code(KeyCode::Alt, PistonKey::LAlt);
+        window.set_key_map(key_map);
+        let left_ctrl = Key { left_ctrl: true, ..tcod_key_from_keycode(KeyCode::Control) };
+        let right_alt = Key { right_alt: true, ..tcod_key_from_keycode(KeyCode::Alt) };
+        window.set_event_source(VecDeque::from(vec![
+            (KEY_PRESS, Event::Key(left_ctrl)),
+            (KEY_PRESS, Event::Key(right_alt)),
+        ]));
+        assert_eq!(window.poll_event(), Some(Input::Press(Button::Keyboard(PistonKey::RCtrl))));
+        assert_eq!(window.poll_event(), Some(Input::Press(Button::Keyboard(PistonKey::LAlt))));
+    }
+
+    #[test]
+    fn test_tcod_map_text() {
+        use super::tcod_map_text;
+
+        let shifted = |c| Key { shift: true, ..tcod_key_from_char(c) };
+        let with_ctrl = |c| Key { left_ctrl: true, ..tcod_key_from_char(c) };
+        let with_alt = |c| Key { left_alt: true, ..tcod_key_from_char(c) };
+        let with_alt_gr = |c| Key { left_ctrl: true, right_alt: true, ..tcod_key_from_char(c) };
+
+        // The case of letters is taken from TCOD as-is, since only it knows
+        // the state of Caps Lock
+        assert_eq!(tcod_map_text(tcod_key_from_char('a')), Some("a".to_owned()));
+        assert_eq!(tcod_map_text(tcod_key_from_char('A')), Some("A".to_owned()));
+        assert_eq!(tcod_map_text(shifted('A')), Some("A".to_owned()));
+        assert_eq!(tcod_map_text(shifted('a')), Some("a".to_owned()));
+        assert_eq!(tcod_map_text(shifted('É')), Some("É".to_owned()));
+        assert_eq!(tcod_map_text(tcod_key_from_char('é')), Some("é".to_owned()));
+        assert_eq!(tcod_map_text(tcod_key_from_char('1')), Some("1".to_owned()));
+        assert_eq!(tcod_map_text(shifted('!')), Some("!".to_owned()));
+        assert_eq!(tcod_map_text(tcod_key_from_char('<')), Some("<".to_owned()));
+        assert_eq!(tcod_map_text(tcod_key_from_char(' ')), Some(" ".to_owned()));
+        assert_eq!(tcod_map_text(with_ctrl('\x13')), None);
+        assert_eq!(tcod_map_text(with_ctrl('s')), None);
+        assert_eq!(tcod_map_text(with_alt('s')), None);
+        assert_eq!(tcod_map_text(with_alt('1')), None);
+        assert_eq!(tcod_map_text(with_alt_gr('\x13')), None);
+        // AltGr characters are typed with Ctrl and right Alt held on Windows
+        assert_eq!(tcod_map_text(with_alt_gr('@')), Some("@".to_owned()));
+        assert_eq!(tcod_map_text(with_alt_gr('€')), Some("€".to_owned()));
+        assert_eq!(tcod_map_text(with_alt_gr('{')), Some("{".to_owned()));
+        assert_eq!(tcod_map_text(tcod_key_from_char('\r')), None);
+        assert_eq!(tcod_map_text(tcod_key_from_keycode(KeyCode::Up)), None);
+    }
+
     #[test]
     fn test_tcod_map_mouse() {
         use self::piston::input::mouse::MouseButton;
@@ -702,4 +2384,120 @@ mod tests {
 
         assert_eq!(tcod_map_mouse(prev_state, &state), MouseButton::Unknown);
     }
+
+    #[test]
+    fn test_tcod_map_mouse_buttons() {
+        use self::piston::input::{Button, Input};
+        use self::piston::input::mouse::MouseButton;
+
+        use super::tcod::input::Mouse;
+        use super::tcod_map_mouse_buttons;
+
+        let prev_state = Mouse {
+            ..Mouse::default()
+        };
+        let state = Mouse {
+            lbutton: true,
+            rbutton: true,
+            mbutton: true,
+            ..Mouse::default()
+        };
+
+        assert_eq!(tcod_map_mouse_buttons(prev_state, &state),
+                   vec![Input::Press(Button::Mouse(MouseButton::Left)),
+                        Input::Press(Button::Mouse(MouseButton::Right)),
+                        Input::Press(Button::Mouse(MouseButton::Middle))]);
+        assert_eq!(tcod_map_mouse_buttons(state, &prev_state),
+                   vec![Input::Release(Button::Mouse(MouseButton::Left)),
+                        Input::Release(Button::Mouse(MouseButton::Right)),
+                        Input::Release(Button::Mouse(MouseButton::Middle))]);
+
+        let prev_state = Mouse {
+            mbutton: true,
+            ..Mouse::default()
+        };
+        let state = Mouse {
+            lbutton: true,
+            mbutton: true,
+            ..Mouse::default()
+        };
+
+        assert_eq!(tcod_map_mouse_buttons(prev_state, &state),
+                   vec![Input::Press(Button::Mouse(MouseButton::Left))]);
+        assert_eq!(tcod_map_mouse_buttons(state, &state), vec![]);
+
+        // Clicks which happened entirely between two events
+        let prev_state = Mouse {
+            rbutton: true,
+            ..Mouse::default()
+        };
+        let state = Mouse {
+            rbutton: true,
+            lbutton_pressed: true,
+            rbutton_pressed: true,
+            ..Mouse::default()
+        };
+
+        assert_eq!(tcod_map_mouse_buttons(prev_state, &state),
+                   vec![Input::Press(Button::Mouse(MouseButton::Left)),
+                        Input::Release(Button::Mouse(MouseButton::Left)),
+                        Input::Release(Button::Mouse(MouseButton::Right)),
+                        Input::Press(Button::Mouse(MouseButton::Right))]);
+
+        // A regular release also sets the `*_pressed` flag
+        let prev_state = Mouse {
+            mbutton: true,
+            ..Mouse::default()
+        };
+        let state = Mouse {
+            mbutton_pressed: true,
+            ..Mouse::default()
+        };
+
+        assert_eq!(tcod_map_mouse_buttons(prev_state, &state),
+                   vec![Input::Release(Button::Mouse(MouseButton::Middle))]);
+    }
+
+    #[test]
+    fn test_coordinate_mode() {
+        use super::CoordinateMode;
+        use super::tcod::input::Mouse;
+
+        let state = Mouse {
+            x: 100,
+            y: 30,
+            cx: 12,
+            cy: 3,
+            ..Mouse::default()
+        };
+
+        assert_eq!(CoordinateMode::Pixels.position(&state, (8, 8)), (100.0, 30.0));
+        assert_eq!(CoordinateMode::Cells.position(&state, (8, 8)), (12.0, 3.0));
+        assert_eq!(CoordinateMode::FractionalCells.position(&state, (8, 8)), (12.5, 3.75));
+    }
+
+    #[test]
+    fn test_tcod_map_scroll() {
+        use self::piston::input::Motion;
+
+        use super::tcod::input::Mouse;
+        use super::tcod_map_scroll;
+
+        let state = Mouse {
+            wheel_up: true,
+            ..Mouse::default()
+        };
+        assert_eq!(tcod_map_scroll(&state), Some(Motion::MouseScroll(0.0, 1.0)));
+
+        let state = Mouse {
+            wheel_down: true,
+            ..Mouse::default()
+        };
+        assert_eq!(tcod_map_scroll(&state), Some(Motion::MouseScroll(0.0, -1.0)));
+
+        let state = Mouse {
+            ..Mouse::default()
+        };
+        assert_eq!(tcod_map_scroll(&state), None);
+    }
 }
@@ -0,0 +1,323 @@
+// Copyright 2015-2016 Nikita Pekin and the tcod_window contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Recording and replaying of raw TCOD input.
+//!
+//! A recording is a UTF-8 text file. The first line holds the format name and
+//! version, e.g. `tcod_window-recording 1`. Every following line holds one
+//! event: the frame it was received in, the milliseconds elapsed since the
+//! recording started, the event type, and the fields of the TCOD `Key` or
+//! `Mouse`, separated by spaces.
+//!
+//! A frame ends whenever the recorded event source runs out of events, i.e.
+//! once per `poll_event` drain of the Piston event loop. Replays are paced by
+//! frame rather than by time, so that they are deterministic.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::str::{FromStr, SplitWhitespace};
+use std::time::{Duration, Instant};
+
+use tcod::input::{Event, EventFlags, KEY_PRESS, KEY_RELEASE, Key, MOUSE_MOVE, MOUSE_PRESS,
+                  MOUSE_RELEASE, Mouse};
+
+use EventSource;
+use keymap::key_code_from_name;
+
+/// The version of the recording format written by `Recorder`.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The name of the recording format, at the start of every recording.
+const FORMAT_NAME: &'static str = "tcod_window-recording";
+
+/// An event source which writes every event of another source to a recording.
+pub struct Recorder<S, W> {
+    source: S,
+    writer: W,
+    frame: u64,
+    start: Instant,
+    error: Option<io::Error>,
+}
+
+impl<S: EventSource, W: Write> Recorder<S, W> {
+    /// Starts a new recording of the events of `source` to `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the recording header could not be written.
+    pub fn new(source: S, mut writer: W) -> io::Result<Self> {
+        try!(writeln!(writer, "{} {}", FORMAT_NAME, FORMAT_VERSION));
+
+        Ok(Recorder {
+            source: source,
+            writer: writer,
+            frame: 0,
+            start: Instant::now(),
+            error: None,
+        })
+    }
+
+    /// Returns the first error encountered while writing the recording.
+    ///
+    /// Nothing more is written once an error has occurred, but events are
+    /// still passed through.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Returns the source whose events are recorded.
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    /// Stops recording, flushing the writer, and returns the source whose
+    /// events were recorded along with the first error encountered while
+    /// writing the recording, if any.
+    pub fn finish(mut self) -> (S, Option<io::Error>) {
+        if self.error.is_none() {
+            self.error = self.writer.flush().err();
+        }
+        (self.source, self.error)
+    }
+
+    /// Records an event received from the source, or the end of a frame if
+    /// there was none, and passes it on.
+    fn record(&mut self, event: Option<(EventFlags, Event)>) -> Option<(EventFlags, Event)> {
+        if self.error.is_none() {
+            let result = match event {
+                Some((flags, ref event)) => self.write_event(flags, event),
+                None => self.writer.flush(),
+            };
+            self.error = result.err();
+        }
+        if event.is_none() {
+            self.frame += 1;
+        }
+
+        event
+    }
+
+    fn write_event(&mut self, flags: EventFlags, event: &Event) -> io::Result<()> {
+        let elapsed = self.start.elapsed();
+        let millis = elapsed.as_secs() * 1000 + (elapsed.subsec_nanos() / 1_000_000) as u64;
+
+        let name = match flags_name(flags) {
+            Some(name) => name,
+            // Events of any other type are never translated, so there is no
+            // point in recording them.
+            None => return Ok(()),
+        };
+
+        try!(write!(self.writer, "{} {} {}", self.frame, millis, name));
+        match *event {
+            Event::Key(ref key) => {
+                write!(self.writer,
+                       " {:?} {} {} {} {} {} {} {}\n",
+                       key.code,
+                       key.printable as u32,
+                       key.pressed as u8,
+                       key.left_alt as u8,
+                       key.left_ctrl as u8,
+                       key.right_alt as u8,
+                       key.right_ctrl as u8,
+                       key.shift as u8)
+            },
+            Event::Mouse(ref mouse) => {
+                write!(self.writer,
+                       " {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}\n",
+                       mouse.x,
+                       mouse.y,
+                       mouse.dx,
+                       mouse.dy,
+                       mouse.cx,
+                       mouse.cy,
+                       mouse.dcx,
+                       mouse.dcy,
+                       mouse.lbutton as u8,
+                       mouse.rbutton as u8,
+                       mouse.mbutton as u8,
+                       mouse.lbutton_pressed as u8,
+                       mouse.rbutton_pressed as u8,
+                       mouse.mbutton_pressed as u8,
+                       mouse.wheel_up as u8,
+                       mouse.wheel_down as u8)
+            },
+        }
+    }
+}
+
+impl<S: EventSource, W: Write> EventSource for Recorder<S, W> {
+    fn next_event(&mut self) -> Option<(EventFlags, Event)> {
+        let event = self.source.next_event();
+        self.record(event)
+    }
+
+    fn wait_event(&mut self, timeout: Option<Duration>) -> Option<(EventFlags, Event)> {
+        let event = self.source.wait_event(timeout);
+        self.record(event)
+    }
+}
+
+/// An event source which plays back a recording made by a `Recorder`.
+pub struct Replay {
+    events: VecDeque<(u64, EventFlags, Event)>,
+    frame: u64,
+}
+
+impl Replay {
+    /// Reads a recording made by a `Recorder`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the recording could not be read, is malformed, or
+    /// was written in an unsupported version of the format.
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut lines = reader.lines();
+
+        let header = try!(lines.next().unwrap_or_else(|| Err(invalid_data("empty recording"))));
+        let mut fields = header.split_whitespace();
+        if fields.next() != Some(FORMAT_NAME) {
+            return Err(invalid_data("not a tcod_window recording"));
+        }
+        let version: u32 = try!(field(&mut fields));
+        if version != FORMAT_VERSION {
+            return Err(invalid_data(&format!("unsupported recording version {}", version)));
+        }
+
+        let mut events = VecDeque::new();
+        for line in lines {
+            let line = try!(line);
+            if line.trim().is_empty() {
+                continue;
+            }
+            events.push_back(try!(parse_event(&line)));
+        }
+
+        Ok(Replay {
+            events: events,
+            frame: 0,
+        })
+    }
+
+    /// Returns whether every recorded event has been played back.
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+impl EventSource for Replay {
+    fn next_event(&mut self) -> Option<(EventFlags, Event)> {
+        match self.events.front() {
+            Some(&(frame, _, _)) if frame <= self.frame => {},
+            _ => {
+                self.frame += 1;
+                return None;
+            },
+        }
+
+        self.events.pop_front().map(|(_, flags, event)| (flags, event))
+    }
+
+    fn wait_event(&mut self, _timeout: Option<Duration>) -> Option<(EventFlags, Event)> {
+        // Skip ahead to the frame of the next recorded event, rather than
+        // waiting for the frames in between to pass.
+        self.events.pop_front().map(|(frame, flags, event)| {
+            self.frame = frame;
+            (flags, event)
+        })
+    }
+}
+
+/// Returns the name under which events of the given type are recorded.
+fn flags_name(flags: EventFlags) -> Option<&'static str> {
+    match flags {
+        KEY_PRESS => Some("key_press"),
+        KEY_RELEASE => Some("key_release"),
+        MOUSE_MOVE => Some("mouse_move"),
+        MOUSE_PRESS => Some("mouse_press"),
+        MOUSE_RELEASE => Some("mouse_release"),
+        _ => None,
+    }
+}
+
+/// Parses a single recorded event, along with the frame it was received in.
+fn parse_event(line: &str) -> io::Result<(u64, EventFlags, Event)> {
+    let mut fields = line.split_whitespace();
+    let frame = try!(field(&mut fields));
+    let _millis: u64 = try!(field(&mut fields));
+    let flags = match fields.next() {
+        Some("key_press") => KEY_PRESS,
+        Some("key_release") => KEY_RELEASE,
+        Some("mouse_move") => MOUSE_MOVE,
+        Some("mouse_press") => MOUSE_PRESS,
+        Some("mouse_release") => MOUSE_RELEASE,
+        _ => return Err(invalid_data(&format!("invalid event type in {:?}", line))),
+    };
+
+    let event = if flags == KEY_PRESS || flags == KEY_RELEASE {
+        let code = try!(fields.next()
+                              .and_then(key_code_from_name)
+                              .ok_or_else(|| invalid_data(&format!("invalid key in {:?}", line))));
+        let printable: u32 = try!(field(&mut fields));
+        let printable = try!(::std::char::from_u32(printable)
+                                 .ok_or_else(|| invalid_data("invalid printable character")));
+
+        Event::Key(Key {
+            code: code,
+            printable: printable,
+            pressed: try!(flag(&mut fields)),
+            left_alt: try!(flag(&mut fields)),
+            left_ctrl: try!(flag(&mut fields)),
+            right_alt: try!(flag(&mut fields)),
+            right_ctrl: try!(flag(&mut fields)),
+            shift: try!(flag(&mut fields)),
+        })
+    } else {
+        Event::Mouse(Mouse {
+            x: try!(field(&mut fields)),
+            y: try!(field(&mut fields)),
+            dx: try!(field(&mut fields)),
+            dy: try!(field(&mut fields)),
+            cx: try!(field(&mut fields)),
+            cy: try!(field(&mut fields)),
+            dcx: try!(field(&mut fields)),
+            dcy: try!(field(&mut fields)),
+            lbutton: try!(flag(&mut fields)),
+            rbutton: try!(flag(&mut fields)),
+            mbutton: try!(flag(&mut fields)),
+            lbutton_pressed: try!(flag(&mut fields)),
+            rbutton_pressed: try!(flag(&mut fields)),
+            mbutton_pressed: try!(flag(&mut fields)),
+            wheel_up: try!(flag(&mut fields)),
+            wheel_down: try!(flag(&mut fields)),
+        })
+    };
+
+    Ok((frame, flags, event))
+}
+
+/// Parses the next field of a recorded line.
+fn field<T: FromStr>(fields: &mut SplitWhitespace) -> io::Result<T> {
+    fields.next()
+          .and_then(|field| field.parse().ok())
+          .ok_or_else(|| invalid_data("missing or invalid field"))
+}
+
+/// Parses the next field of a recorded line as a `0` or `1` flag.
+fn flag(fields: &mut SplitWhitespace) -> io::Result<bool> {
+    match fields.next() {
+        Some("0") => Ok(false),
+        Some("1") => Ok(true),
+        _ => Err(invalid_data("missing or invalid flag")),
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
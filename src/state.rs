@@ -0,0 +1,96 @@
+// Copyright 2015-2016 Nikita Pekin and the tcod_window contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashSet;
+
+use input::{Button, Input, MouseButton};
+use input::keyboard::{self, Key, ModifierKey};
+use tcod::input::Mouse;
+
+/// The keys and mouse buttons currently held, and the position of the mouse,
+/// as seen through the events returned by a `TcodWindow`.
+#[derive(Clone, Debug, Default)]
+pub struct InputState {
+    keys: HashSet<Key>,
+    mouse_buttons: HashSet<MouseButton>,
+    mouse_position: (f64, f64),
+    mouse_cell: (i32, i32),
+}
+
+impl InputState {
+    /// Returns whether the given key is held down.
+    pub fn is_key_down(&self, key: Key) -> bool {
+        self.keys.contains(&key)
+    }
+
+    /// Returns whether the given mouse button is held down.
+    pub fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.contains(&button)
+    }
+
+    /// Returns the last known position of the mouse, in pixels.
+    pub fn mouse_position(&self) -> (f64, f64) {
+        self.mouse_position
+    }
+
+    /// Returns the console cell the mouse was last known to be over.
+    pub fn mouse_cell(&self) -> (i32, i32) {
+        self.mouse_cell
+    }
+
+    /// Returns the modifier keys which are held down.
+    pub fn modifiers(&self) -> ModifierKey {
+        let mut modifiers = keyboard::NO_MODIFIER;
+        for key in &self.keys {
+            modifiers.insert(match *key {
+                Key::LCtrl | Key::RCtrl => keyboard::CTRL,
+                Key::LShift | Key::RShift => keyboard::SHIFT,
+                Key::LAlt | Key::RAlt => keyboard::ALT,
+                Key::LGui | Key::RGui => keyboard::GUI,
+                _ => keyboard::NO_MODIFIER,
+            });
+        }
+        modifiers
+    }
+
+    /// Updates the held keys and mouse buttons from a translated event.
+    ///
+    /// Losing focus releases every key and mouse button, since their release
+    /// events go to another window.
+    pub fn update(&mut self, input: &Input) {
+        match *input {
+            Input::Press(Button::Keyboard(key)) => {
+                self.keys.insert(key);
+            },
+            Input::Release(Button::Keyboard(key)) => {
+                self.keys.remove(&key);
+            },
+            Input::Press(Button::Mouse(button)) => {
+                self.mouse_buttons.insert(button);
+            },
+            Input::Release(Button::Mouse(button)) => {
+                self.mouse_buttons.remove(&button);
+            },
+            Input::Focus(false) => self.release_all(),
+            _ => {},
+        }
+    }
+
+    /// Updates the position of the mouse from a raw TCOD mouse event.
+    pub fn update_mouse(&mut self, state: &Mouse) {
+        self.mouse_position = (state.x as f64, state.y as f64);
+        self.mouse_cell = (state.cx as i32, state.cy as i32);
+    }
+
+    /// Forgets every held key and mouse button.
+    pub fn release_all(&mut self) {
+        self.keys.clear();
+        self.mouse_buttons.clear();
+    }
+}
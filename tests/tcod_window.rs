@@ -9,7 +9,7 @@ use tcod_window::TcodWindow;
 
 #[test]
 fn test_tcod_window() {
-    let mut window = TcodWindow::new(
+    let mut window = TcodWindow::headless(
         WindowSettings::new(
            "My Application".to_owned(),
             Size {